@@ -0,0 +1,81 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+use supaterm::{
+    editor::{Editor, History},
+    style::{Color, ContentStyle},
+    text::Span,
+    self as st
+};
+
+use std::io::{self, Write};
+
+const COMMANDS: [&str; 5] = ["help", "hello", "history", "quit", "quiet"];
+
+/// Makes known commands green
+fn highlight(input: &str) -> Vec<Span> {
+    input.split_inclusive(' ')
+        .map(|word| match COMMANDS.contains(&word.trim_end()) {
+            true => Span::new(word, ContentStyle::new().foreground(Color::Green)),
+            false => Span::plain(word),
+        })
+        .collect()
+}
+
+fn complete(input: &str, cursor: usize) -> (usize, Vec<String>) {
+    let start = input[..cursor].rfind(' ').map_or(0, |index| index + 1);
+    let word = &input[start..cursor];
+
+    let candidates = COMMANDS.iter()
+        .filter(|command| command.starts_with(word))
+        .map(|command| command.to_string())
+        .collect();
+
+    (start, candidates)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+
+    let mut term = st::Terminal::new(
+        io::stdin().lock(),
+        io::stdout().lock()
+    )?;
+
+    let history = History::load(std::env::temp_dir().join("supaterm_line_editor_history"))?;
+
+    let mut editor = Editor::new()
+        .with_history(history)
+        .with_highlighter(highlight)
+        .with_completer(complete);
+
+    loop {
+        match editor.read_line(&mut term, "> ") {
+            Ok(Some(line)) if line == "quit" => break,
+            Ok(Some(line)) => writeln!(term, "you typed: {line:?}")?,
+            Ok(None) => break,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => writeln!(term, "^C")?,
+            Err(error) => return Err(error.into()),
+        }
+
+        if let Some(error) = editor.take_history_error() {
+            writeln!(term, "couldn't save the history: {error}")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! The standard base64 encoding with padding, which terminals use for binary data in escape
+//! sequences
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+
+        // a chunk of n bytes is encoded as n + 1 characters, with the rest being padding
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => encoded.push(ALPHABET[(group >> (18 - index * 6)) as usize & 0x3F] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decodes `text`, ignoring padding. Returns `None` if it has any other characters which aren't
+/// part of the encoding.
+pub(crate) fn decode(text: &[u8]) -> Option<Vec<u8>> {
+    let values: Vec<u32> = text.iter()
+        .take_while(|byte| **byte != b'=')
+        .map(|byte| ALPHABET.iter().position(|c| c == byte).map(|value| value as u32))
+        .collect::<Option<_>>()?;
+
+    let mut decoded = Vec::with_capacity(values.len() * 3 / 4);
+
+    for chunk in values.chunks(4) {
+        let group = chunk.iter().enumerate().fold(0, |group, (index, value)| group | value << (18 - index * 6));
+
+        // n characters are n - 1 bytes
+        for index in 0..chunk.len().saturating_sub(1) {
+            decoded.push((group >> (16 - index * 8)) as u8);
+        }
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foob"), "Zm9vYg==");
+
+        assert_eq!(decode(b"Zg=="), Some(b"f".to_vec()));
+        assert_eq!(decode(b"Zm8="), Some(b"fo".to_vec()));
+        assert_eq!(decode(b"Zm9v"), Some(b"foo".to_vec()));
+        // padding is optional
+        assert_eq!(decode(b"Zg"), Some(b"f".to_vec()));
+        assert_eq!(decode(b"Zm9vYg"), Some(b"foob".to_vec()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        for length in 0..bytes.len() {
+            assert_eq!(decode(encode(&bytes[..length]).as_bytes()), Some(bytes[..length].to_vec()));
+        }
+    }
+
+    #[test]
+    fn invalid_characters() {
+        assert_eq!(decode(b"Zm9*"), None);
+        assert_eq!(decode(b"Zm9v\n"), None);
+        assert_eq!(decode(b"Zm-_"), None);
+    }
+}
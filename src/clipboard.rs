@@ -0,0 +1,153 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Using the clipboard of the machine the terminal runs on, with the operating system command for
+//! selections (OSC 52)
+//!
+//! This works over SSH, since the terminal rather than the program accesses the clipboard. Many
+//! terminals allow copying but not reading, or ask the user first, so reading with
+//! [Terminal::read_clipboard](crate::Terminal::read_clipboard) is best-effort.
+
+use {
+    crate::{ansi_capability, base64, command::{write_extended, Context}, Command},
+
+    std::io,
+
+    terminfo::Database,
+};
+
+/// Which of the terminal's selections to use
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum Selection {
+    /// The clipboard, which is pasted with Ctrl-V or similar
+    #[default]
+    Clipboard,
+    /// The primary selection on X11 and Wayland, which is the text most recently selected and is
+    /// pasted with the middle mouse button
+    Primary,
+    Secondary,
+}
+
+impl Selection {
+    const fn code(self) -> &'static str {
+        match self {
+            Self::Clipboard => "c",
+            Self::Primary => "p",
+            Self::Secondary => "q",
+        }
+    }
+}
+
+/// Copies `data` to `selection`
+///
+/// The terminfo `Ms` capability is used if the database has it. Terminals which don't support
+/// this, or have it disabled, ignore it.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct CopyToClipboard {
+    pub selection: Selection,
+    pub data: Vec<u8>,
+}
+
+impl CopyToClipboard {
+    /// Copies `data` to the clipboard
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            selection: Selection::Clipboard,
+            data: data.into(),
+        }
+    }
+
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+}
+
+impl Command for CopyToClipboard {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.data.len().div_ceil(3) * 4 + 10)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let selection = self.selection.code();
+        let data = base64::encode(&self.data);
+        let fallback = format!("\x1b]52;{selection};{data}\x1b\\");
+
+        write_extended(database, ctx, target, "Ms", &[selection.into(), data.into()], fallback.as_bytes())
+    }
+}
+
+ansi_capability!(CopyToClipboard, extended: "Ms");
+
+/// Asks the terminal for the contents of `self.0`. See
+/// [Terminal::read_clipboard](crate::Terminal::read_clipboard).
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct RequestClipboard(pub Selection);
+
+impl Command for RequestClipboard {
+    fn size_hint(&self) -> Option<usize> {
+        Some(10)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write!(target, "\x1b]52;{};?\x1b\\", self.0.code())
+    }
+}
+
+ansi_capability!(RequestClipboard);
+
+/// Parses a reply to [RequestClipboard], such as `ESC ] 52 ; c ; aGVsbG8= ESC \`, into the data.
+/// Terminals which don't allow reading may reply with no data, which gives `Some(None)`.
+pub(crate) fn parse_clipboard_report(reply: &[u8]) -> Option<Option<Vec<u8>>> {
+    let body = reply.strip_prefix(b"\x1b]52;")?;
+    let body = body.strip_suffix(b"\x1b\\").or_else(|| body.strip_suffix(b"\x07"))?;
+
+    // the selection replied with may differ from the one asked for, so it is skipped
+    let data = &body[body.iter().position(|byte| *byte == b';')? + 1..];
+
+    match data {
+        [] | b"?" => Some(None),
+        data => Some(base64::decode(data)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipboard_reports() {
+        assert_eq!(parse_clipboard_report(b"\x1b]52;c;aGVsbG8=\x1b\\"), Some(Some(b"hello".to_vec())));
+        assert_eq!(parse_clipboard_report(b"\x1b]52;c;aGVsbG8=\x07"), Some(Some(b"hello".to_vec())));
+        // the selection replied with is ignored
+        assert_eq!(parse_clipboard_report(b"\x1b]52;p;aGk=\x07"), Some(Some(b"hi".to_vec())));
+
+        // terminals which don't allow reading reply with no data
+        assert_eq!(parse_clipboard_report(b"\x1b]52;c;\x07"), Some(None));
+        assert_eq!(parse_clipboard_report(b"\x1b]52;c;?\x1b\\"), Some(None));
+    }
+
+    #[test]
+    fn malformed_clipboard_reports() {
+        assert_eq!(parse_clipboard_report(b"\x1b]52;c;aGVsbG8="), None);
+        assert_eq!(parse_clipboard_report(b"\x1b]52;aGVsbG8=\x07"), None);
+        assert_eq!(parse_clipboard_report(b"\x1b]4;c;aGVsbG8=\x07"), None);
+        // data which isn't base64 is treated like no data
+        assert_eq!(parse_clipboard_report(b"\x1b]52;c;aGV*bG8=\x07"), Some(None));
+    }
+}
@@ -16,7 +16,13 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>
 */
 
-use std::io;
+use {
+    crate::{misc::NotificationProtocol, palette::Palette, sys},
+
+    std::{io, ops},
+
+    terminfo::capability as cap,
+};
 
 pub trait Command: Capability {
     fn size_hint(&self) -> Option<usize>;
@@ -24,7 +30,7 @@ pub trait Command: Capability {
     fn write_to(
         &self,
         database: &terminfo::Database,
-        ctx: &mut terminfo::expand::Context,
+        ctx: &mut Context,
         target: &mut dyn io::Write
     ) -> io::Result<()>;
 }
@@ -34,4 +40,564 @@ pub trait Capability {
         &self,
         database: &terminfo::Database,
     ) -> bool;
-}
\ No newline at end of file
+}
+
+/// What commands are written with besides the terminfo database
+///
+/// This holds the variables capabilities can set and read, which the context dereferences to, and
+/// the settings of the [Terminal](crate::Terminal) commands are written to. Every command written
+/// to the same terminal should be given the same context.
+#[derive(Debug)]
+pub struct Context {
+    variables: terminfo::expand::Context,
+    baud_rate: u32,
+    palette: Palette,
+    inside_tmux: bool,
+    notification_protocol: Option<NotificationProtocol>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self {
+            variables: terminfo::expand::Context::default(),
+            baud_rate: 38400,
+            palette: Palette::XTERM,
+            inside_tmux: false,
+            notification_protocol: None,
+        }
+    }
+
+    /// The baud rate (the speed of the connection to the terminal in bits per second) padding in
+    /// terminfo capabilities is worked out for. This is 38400 by default, which is what
+    /// pseudo-terminals report.
+    ///
+    /// Some terminals, mostly ones connected over a serial line, need time to carry out some
+    /// capabilities, which their terminfo entry gives as a delay such as `$<5>`. The delay is made
+    /// by writing padding characters, so the number of them depends on the baud rate.
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    pub fn set_baud_rate(&mut self, baud_rate: u32) {
+        self.baud_rate = baud_rate;
+    }
+
+    /// The palette [Color::Rgb](crate::style::Color::Rgb) is matched against on terminals which
+    /// don't support direct color, which is [Palette::XTERM] by default
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Sets the palette [Color::Rgb](crate::style::Color::Rgb) is matched against, without changing
+    /// the terminal's colors
+    pub fn use_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    /// Returns true if the commands are written to tmux, which has to be asked to pass some of
+    /// them on to the terminal it runs in. This is false by default.
+    pub fn inside_tmux(&self) -> bool {
+        self.inside_tmux
+    }
+
+    pub fn set_inside_tmux(&mut self, inside_tmux: bool) {
+        self.inside_tmux = inside_tmux;
+    }
+
+    /// The protocol [Notify](crate::misc::Notify) uses, which is worked out from the database
+    /// alone if it hasn't been detected
+    pub(crate) fn notification_protocol(&self) -> Option<NotificationProtocol> {
+        self.notification_protocol
+    }
+
+    pub(crate) fn set_notification_protocol(&mut self, protocol: NotificationProtocol) {
+        self.notification_protocol = Some(protocol);
+    }
+}
+
+impl ops::Deref for Context {
+    type Target = terminfo::expand::Context;
+
+    fn deref(&self) -> &Self::Target {
+        &self.variables
+    }
+}
+
+impl ops::DerefMut for Context {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.variables
+    }
+}
+
+impl<C: Capability + ?Sized> Capability for &C {
+    fn is_supported(&self, database: &terminfo::Database) -> bool {
+        (**self).is_supported(database)
+    }
+}
+
+impl<C: Command + ?Sized> Command for &C {
+    fn size_hint(&self) -> Option<usize> {
+        (**self).size_hint()
+    }
+
+    fn write_to(&self, database: &terminfo::Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        (**self).write_to(database, ctx, target)
+    }
+}
+
+impl<C: Capability + ?Sized> Capability for Box<C> {
+    fn is_supported(&self, database: &terminfo::Database) -> bool {
+        (**self).is_supported(database)
+    }
+}
+
+/// Lets commands of different types be kept together as `Box<dyn Command>`
+impl<C: Command + ?Sized> Command for Box<C> {
+    fn size_hint(&self) -> Option<usize> {
+        (**self).size_hint()
+    }
+
+    fn write_to(&self, database: &terminfo::Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        (**self).write_to(database, ctx, target)
+    }
+}
+
+/// A `None` command writes nothing and is always supported
+impl<C: Capability> Capability for Option<C> {
+    fn is_supported(&self, database: &terminfo::Database) -> bool {
+        self.as_ref().is_none_or(|capability| capability.is_supported(database))
+    }
+}
+
+impl<C: Command> Command for Option<C> {
+    fn size_hint(&self) -> Option<usize> {
+        match self {
+            Some(command) => command.size_hint(),
+            None => Some(0),
+        }
+    }
+
+    fn write_to(&self, database: &terminfo::Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        match self {
+            Some(command) => command.write_to(database, ctx, target),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A list of commands is supported if every command in it is
+impl<C: Capability> Capability for [C] {
+    fn is_supported(&self, database: &terminfo::Database) -> bool {
+        self.iter().all(|capability| capability.is_supported(database))
+    }
+}
+
+/// The commands are written in order, stopping at the first error
+impl<C: Command> Command for [C] {
+    fn size_hint(&self) -> Option<usize> {
+        self.iter().map(Command::size_hint).sum()
+    }
+
+    fn write_to(&self, database: &terminfo::Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        self.iter().try_for_each(|command| command.write_to(database, ctx, target))
+    }
+}
+
+impl<C: Capability> Capability for Vec<C> {
+    fn is_supported(&self, database: &terminfo::Database) -> bool {
+        self.as_slice().is_supported(database)
+    }
+}
+
+impl<C: Command> Command for Vec<C> {
+    fn size_hint(&self) -> Option<usize> {
+        self.as_slice().size_hint()
+    }
+
+    fn write_to(&self, database: &terminfo::Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        self.as_slice().write_to(database, ctx, target)
+    }
+}
+
+/// Implements `Command` and `Capability` for tuples of commands, which combine several commands
+/// into one the same way as slices
+macro_rules! impl_for_tuples {
+    ($(($($name:ident $index:tt),+)),+ $(,)?) => {$(
+        impl<$($name: Capability),+> Capability for ($($name,)+) {
+            fn is_supported(&self, database: &terminfo::Database) -> bool {
+                $(self.$index.is_supported(database))&&+
+            }
+        }
+
+        impl<$($name: Command),+> Command for ($($name,)+) {
+            fn size_hint(&self) -> Option<usize> {
+                Some(0 $(+ self.$index.size_hint()?)+)
+            }
+
+            fn write_to(&self, database: &terminfo::Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+                $(self.$index.write_to(database, ctx, target)?;)+
+                Ok(())
+            }
+        }
+    )+};
+}
+
+impl_for_tuples!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11),
+);
+
+/// Converts an error from expanding a terminfo capability into an `io::Error`
+///
+/// The `ErrorKind`s match the ones documented under "`Command` implementation errors" on the
+/// commands.
+pub(crate) fn expansion_error(error: terminfo::Error) -> io::Error {
+    match error {
+        terminfo::Error::Io(io_error) => io_error,
+        terminfo::Error::NotFound => io::Error::new(io::ErrorKind::NotFound, error),
+        terminfo::Error::Parse => io::Error::new(io::ErrorKind::InvalidData, error),
+        terminfo::Error::Expand(_) => io::Error::other(error),
+    }
+}
+
+/// Writes the user defined capability `name`, which terminfo has no type for, expanded with
+/// `parameters`, or writes `fallback` if the database doesn't have it
+pub(crate) fn write_extended(
+    database: &terminfo::Database,
+    ctx: &mut Context,
+    target: &mut dyn io::Write,
+    name: &str,
+    parameters: &[terminfo::expand::Parameter],
+    fallback: &[u8],
+) -> io::Result<()> {
+    use terminfo::Expand;
+
+    match database.raw(name) {
+        Some(terminfo::Value::String(value)) => write_expanded(database, ctx, target, 1, |expanded, ctx| value.expand(expanded, parameters, ctx)),
+        _ => target.write_all(fallback),
+    }
+}
+
+/// Returns true if `database` describes a terminal which understands ANSI escape sequences, so
+/// the sequences xterm uses can be written in place of capabilities it doesn't have
+///
+/// This is taken to be the case if the terminal moves the cursor with a control sequence (`cup`
+/// starts with `ESC [` or CSI) and isn't a printing (`hc`) or generic (`gn`) terminal, which would
+/// show the sequences as text.
+pub(crate) fn is_ansi_terminal(database: &terminfo::Database) -> bool {
+    let hard_copy = database.get::<cap::HardCopy>().is_some_and(|hc| hc.0);
+    let generic = database.get::<cap::GenericType>().is_some_and(|gn| gn.0);
+
+    let control_sequences = database.get::<cap::CursorAddress>().is_some_and(|cup| {
+        let cup: &[u8] = cup.as_ref();
+        cup.starts_with(b"\x1b[") || cup.starts_with(&[0x9B])
+    });
+
+    control_sequences && !hard_copy && !generic
+}
+
+/// Expands a capability with `expand` and writes it to `target` with its padding handled, see
+/// [write_padded]
+pub(crate) fn write_expanded(
+    database: &terminfo::Database,
+    ctx: &mut Context,
+    target: &mut dyn io::Write,
+    affected_lines: u32,
+    expand: impl FnOnce(&mut Vec<u8>, &mut terminfo::expand::Context) -> terminfo::Result<()>,
+) -> io::Result<()> {
+    let mut expanded = Vec::new();
+    expand(&mut expanded, &mut ctx.variables).map_err(expansion_error)?;
+
+    write_padded(database, &expanded, affected_lines, ctx.baud_rate, target)
+}
+
+/// Writes the expanded capability `capability`, replacing each delay (`$<N>`) in it with the
+/// padding it needs at `baud_rate`
+///
+/// Following the terminfo rules:
+///
+/// - delays ending in `*` are multiplied by `affected_lines`
+/// - delays ending in `/` are mandatory. Others are only made if the terminal doesn't use XON/XOFF
+///   flow control (`xon`) and the baud rate is at least the one it needs padding from (`pb`), or
+///   it doesn't have `pb`.
+/// - the padding character is the first one of `pad`, or NUL. Terminals without one (`npc`) are
+///   given the time by flushing `target` and sleeping instead.
+pub(crate) fn write_padded(database: &terminfo::Database, capability: &[u8], affected_lines: u32, baud_rate: u32, target: &mut dyn io::Write) -> io::Result<()> {
+    // capabilities without delays, which is nearly all of them, are written as they are
+    if !capability.windows(2).any(|window| window == b"$<") {
+        return target.write_all(capability);
+    }
+
+    for part in pad(database, capability, affected_lines, baud_rate) {
+        match part {
+            Padded::Bytes(bytes) => target.write_all(&bytes)?,
+            Padded::Sleep(delay) => {
+                target.flush()?;
+                std::thread::sleep(delay);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A part of a capability once its delays have been replaced, see [pad]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Padded {
+    Bytes(Vec<u8>),
+    /// a delay on a terminal without a padding character
+    Sleep(std::time::Duration),
+}
+
+/// Replaces each delay in `capability` with the padding it needs at `baud_rate`, see [write_padded]
+fn pad(database: &terminfo::Database, capability: &[u8], affected_lines: u32, baud_rate: u32) -> Vec<Padded> {
+    // like ncurses, a terminal without `pb` is padded at any baud rate
+    let padding_needed = !database.get::<cap::XonXoff>().is_some_and(|xon| xon.0)
+        && database.get::<cap::PaddingBaudRate>().is_none_or(|minimum| baud_rate as i64 >= minimum.0 as i64);
+
+    let pad_character = match database.get::<cap::PadChar>() {
+        Some(pad) => AsRef::<[u8]>::as_ref(&pad).first().copied().unwrap_or(0),
+        None => 0,
+    };
+
+    let no_pad_character = database.get::<cap::NoPadChar>().is_some_and(|npc| npc.0);
+
+    let mut parts = Vec::new();
+    let mut output = Vec::with_capacity(capability.len());
+    let mut rest = capability;
+
+    while let Some(start) = rest.windows(2).position(|window| window == b"$<") {
+        output.extend_from_slice(&rest[..start]);
+
+        let delay = rest[start + 2..].iter()
+            .position(|byte| *byte == b'>')
+            .and_then(|end| Some((parse_delay(&rest[start + 2..start + 2 + end])?, start + 3 + end)));
+
+        let Some(((tenths, proportional, mandatory), end)) = delay else {
+            // anything which isn't a delay is written as it is
+            output.extend_from_slice(b"$<");
+            rest = &rest[start + 2..];
+            continue;
+        };
+
+        rest = &rest[end..];
+
+        if !(mandatory || padding_needed) {
+            continue;
+        }
+
+        let tenths = match proportional {
+            true => tenths as u64 * affected_lines.max(1) as u64,
+            false => tenths as u64,
+        };
+
+        if no_pad_character {
+            if !output.is_empty() {
+                parts.push(Padded::Bytes(std::mem::take(&mut output)));
+            }
+            parts.push(Padded::Sleep(std::time::Duration::from_micros(tenths * 100)));
+            continue;
+        }
+
+        // a character takes 9 bits to send (with a start bit and a stop bit), like ncurses assumes
+        let count = tenths * baud_rate as u64 / 90_000;
+        output.extend(std::iter::repeat_n(pad_character, count as usize));
+    }
+
+    output.extend_from_slice(rest);
+
+    if !output.is_empty() {
+        parts.push(Padded::Bytes(output));
+    }
+
+    parts
+}
+
+/// Parses the inside of a delay, such as `5`, `2.5*` or `100/`, into the delay in tenths of a
+/// millisecond and whether it is proportional (`*`) and mandatory (`/`)
+fn parse_delay(delay: &[u8]) -> Option<(u32, bool, bool)> {
+    let suffix_start = delay.iter().position(|byte| matches!(byte, b'*' | b'/')).unwrap_or(delay.len());
+    let (number, suffix) = delay.split_at(suffix_start);
+
+    if !suffix.iter().all(|byte| matches!(byte, b'*' | b'/')) {
+        return None;
+    }
+
+    let number = std::str::from_utf8(number).ok()?;
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+
+    if whole.is_empty() || !whole.bytes().all(|byte| byte.is_ascii_digit()) || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    // only one decimal place is significant
+    let tenths = fraction.bytes().next().map_or(0, |digit| (digit - b'0') as u32);
+    let tenths = whole.parse::<u32>().ok()?.checked_mul(10)?.checked_add(tenths)?;
+
+    Some((tenths, suffix.contains(&b'*'), suffix.contains(&b'/')))
+}
+
+/// The number of lines on the screen, which capabilities affecting the whole screen have their
+/// proportional delays multiplied by
+pub(crate) fn screen_lines(database: &terminfo::Database) -> u32 {
+    match sys::window_size() {
+        Ok(size) if size.rows > 0 => size.rows as u32,
+        _ => database.get::<cap::Lines>().map_or(1, |lines| lines.0.max(1) as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A terminal which needs padding from 1200 baud, padded with `*`
+    fn database(extra: &[(&str, terminfo::Value)]) -> terminfo::Database {
+        let mut database = terminfo::Database::new();
+        database.name("test").raw("pb", 1200).raw("pad", "*");
+
+        for (name, value) in extra {
+            database.raw(name, value.clone());
+        }
+
+        database.build().unwrap()
+    }
+
+    /// The padded capability, which mustn't have any sleeps in it
+    fn padded(database: &terminfo::Database, capability: &[u8], affected_lines: u32, baud_rate: u32) -> Vec<u8> {
+        pad(database, capability, affected_lines, baud_rate).into_iter()
+            .flat_map(|part| match part {
+                Padded::Bytes(bytes) => bytes,
+                Padded::Sleep(delay) => panic!("unexpected sleep for {delay:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ansi_terminals() {
+        let database = |capabilities: &[(&str, terminfo::Value)]| {
+            let mut database = terminfo::Database::new();
+            database.name("test");
+
+            for (name, value) in capabilities {
+                database.raw(name, value.clone());
+            }
+
+            database.build().unwrap()
+        };
+
+        let ansi_cup = ("cup", terminfo::Value::from(b"\x1b[%i%p1%d;%p2%dH".as_slice()));
+
+        assert!(is_ansi_terminal(&database(std::slice::from_ref(&ansi_cup))));
+        assert!(is_ansi_terminal(&database(&[("cup", terminfo::Value::from(b"\x9b%i%p1%d;%p2%dH".as_slice()))])));
+
+        // dumb terminals can't move the cursor, and VT52s don't use control sequences for it
+        assert!(!is_ansi_terminal(&database(&[])));
+        assert!(!is_ansi_terminal(&database(&[("cup", terminfo::Value::from(b"\x1bY%p1%' '%+%c%p2%' '%+%c".as_slice()))])));
+
+        assert!(!is_ansi_terminal(&database(&[ansi_cup.clone(), ("gn", terminfo::Value::True)])));
+        assert!(!is_ansi_terminal(&database(&[ansi_cup, ("hc", terminfo::Value::True)])));
+    }
+
+    #[test]
+    fn delays() {
+        assert_eq!(parse_delay(b"5"), Some((50, false, false)));
+        assert_eq!(parse_delay(b"2.5*"), Some((25, true, false)));
+        assert_eq!(parse_delay(b"100/"), Some((1000, false, true)));
+        assert_eq!(parse_delay(b"1.25*/"), Some((12, true, true)));
+        assert_eq!(parse_delay(b"3/*"), Some((30, true, true)));
+
+        assert_eq!(parse_delay(b""), None);
+        assert_eq!(parse_delay(b"x"), None);
+        assert_eq!(parse_delay(b".5"), None);
+        assert_eq!(parse_delay(b"5*x"), None);
+        assert_eq!(parse_delay(b"-1"), None);
+    }
+
+    #[test]
+    fn padding_depends_on_the_baud_rate() {
+        let database = database(&[]);
+
+        // 3ms takes 3.2 characters at 9600 baud and 12.8 at 38400
+        assert_eq!(padded(&database, b"\x1b[K$<3>", 1, 9600), b"\x1b[K***");
+        assert_eq!(padded(&database, b"\x1b[K$<3>", 1, 38400), b"\x1b[K************");
+
+        // 2ms for each of 10 lines
+        assert_eq!(padded(&database, b"\x1b[H\x1b[J$<2*>", 10, 9600), [b"\x1b[H\x1b[J".as_slice(), &[b'*'; 21]].concat());
+        assert_eq!(padded(&database, b"a$<1>b$<1>c", 1, 9600), b"a*b*c");
+    }
+
+    #[test]
+    fn mandatory_padding() {
+        let database = database(&[]);
+
+        // below `pb`, only mandatory delays are padded
+        assert_eq!(padded(&database, b"\x1b[K$<100>", 1, 300), b"\x1b[K");
+        assert_eq!(padded(&database, b"\x1b[K$<100/>", 1, 300), b"\x1b[K***");
+
+        // with XON/XOFF flow control, the same goes for any baud rate
+        let database = self::database(&[("xon", terminfo::Value::True)]);
+
+        assert_eq!(padded(&database, b"\x1b[K$<3>", 1, 9600), b"\x1b[K");
+        assert_eq!(padded(&database, b"\x1b[K$<3/>", 1, 9600), b"\x1b[K***");
+    }
+
+    #[test]
+    fn no_pad_character() {
+        let database = database(&[("npc", terminfo::Value::True)]);
+        let sleep = |micros| Padded::Sleep(std::time::Duration::from_micros(micros));
+
+        // delays are made by sleeping instead, including mandatory ones below `pb`
+        assert_eq!(pad(&database, b"\x1b[K$<3>", 1, 9600), [Padded::Bytes(b"\x1b[K".to_vec()), sleep(3000)]);
+        assert_eq!(pad(&database, b"a$<2.5/>b", 1, 300), [Padded::Bytes(b"a".to_vec()), sleep(2500), Padded::Bytes(b"b".to_vec())]);
+        assert_eq!(pad(&database, b"$<1*>$<1>", 3, 300), []);
+        assert_eq!(pad(&database, b"$<1*>$<1>", 3, 9600), [sleep(3000), sleep(1000)]);
+    }
+
+    #[test]
+    fn no_padding_baud_rate() {
+        let mut database = terminfo::Database::new();
+        database.name("test").raw("pad", "*");
+        let database = database.build().unwrap();
+
+        // without `pb`, padding is needed at any baud rate
+        assert_eq!(padded(&database, b"\x1b[K$<3>", 1, 9600), b"\x1b[K***");
+        assert_eq!(padded(&database, b"\x1b[K$<30>", 1, 300), b"\x1b[K*");
+    }
+
+    #[test]
+    fn default_pad_character() {
+        let mut database = terminfo::Database::new();
+        database.name("test").raw("pb", 1200);
+        let database = database.build().unwrap();
+
+        assert_eq!(padded(&database, b"\x1b[K$<3>", 1, 9600), b"\x1b[K\0\0\0");
+    }
+
+    #[test]
+    fn text_which_is_not_a_delay() {
+        let database = database(&[]);
+
+        assert_eq!(padded(&database, b"$<x>$<3", 1, 9600), b"$<x>$<3");
+
+        let mut output = Vec::new();
+        write_padded(&database, b"no delays", 1, 9600, &mut output).unwrap();
+        assert_eq!(output, b"no delays");
+    }
+}
@@ -0,0 +1,429 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+use {
+    crate::{
+        ansi_capability,
+        command::{is_ansi_terminal, write_expanded, write_extended, Context},
+        define,
+        Command,
+        Capability,
+        RestoreGuard,
+    },
+
+    std::io,
+
+    terminfo::{capability as cap, Database},
+};
+
+define!(custom-impl
+    /// Moves the cursor to column `self.0` and row `self.1`, where (0, 0) is the top left cell
+    definition: pub struct MoveTo(pub u16, pub u16),
+    capability: cap::CursorAddress,
+    size_hint: Some(12),
+    unsupported_msg: "Moving the cursor to a position (terminfo cap-name 'cup') is unsupported in this terminal",
+    write_to_impl: |self, database, capability, ctx, target| {
+        write_expanded(database, ctx, target, 1, |expanded, ctx| {
+            capability.expand()
+                .parameters(self.1 as u32, self.0 as u32)
+                .with(ctx)
+                .to(expanded)
+        })?;
+    },
+    is_supported_impl: |self, database, capability| {
+        true
+    },
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Moves the cursor to the start of the current line
+    definition: pub struct MoveToLineStart,
+    capability: cap::CarriageReturn,
+    size_hint: Some(1),
+    unsupported_msg: "Carriage return (terminfo cap-name 'cr') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Makes the cursor invisible (see [Show])
+    definition: pub struct Hide,
+    capability: cap::CursorInvisible,
+    size_hint: Some(8),
+    unsupported_msg: "Hiding the cursor (terminfo cap-name 'civis') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Makes the cursor visible again after it has been hidden with [Hide]
+    definition: pub struct Show,
+    capability: cap::CursorNormal,
+    size_hint: Some(16),
+    unsupported_msg: "Showing the cursor (terminfo cap-name 'cnorm') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Saves the current cursor position so it can be restored with [RestorePosition]
+    definition: pub struct SavePosition,
+    capability: cap::SaveCursor,
+    size_hint: Some(4),
+    unsupported_msg: "Saving the cursor position (terminfo cap-name 'sc') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Moves the cursor back to the position saved with [SavePosition]
+    definition: pub struct RestorePosition,
+    capability: cap::RestoreCursor,
+    size_hint: Some(4),
+    unsupported_msg: "Restoring the cursor position (terminfo cap-name 'rc') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+/// Defines a command which moves the cursor by some number of cells in one direction, using the
+/// parameterised capability if there is one and otherwise repeating the single step capability
+macro_rules! define_relative_move {
+    (
+        $(#[$attrs:meta])*
+        definition: pub struct $typ:ident,
+        parameterised: $parameterised:ty,
+        single_step: $single_step:ty,
+        unsupported_msg: $unsupported_msg:literal $(,)?
+    ) => {
+        $(#[$attrs])*
+        ///
+        /// Moving by 0 cells does nothing.
+        #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+        pub struct $typ(pub u16);
+
+        impl Command for $typ {
+            fn size_hint(&self) -> Option<usize> {
+                Some(8)
+            }
+
+            fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+                // many terminals treat a count of 0 as 1, so nothing is written at all
+                if self.0 == 0 {
+                    return Ok(());
+                }
+
+                if let Some(capability) = database.get::<$parameterised>() {
+                    return write_expanded(database, ctx, target, 1, |expanded, ctx| {
+                        capability.expand()
+                            .count(self.0 as u32)
+                            .with(ctx)
+                            .to(expanded)
+                    });
+                }
+
+                match database.get::<$single_step>() {
+                    Some(capability) => {
+                        for _ in 0..self.0 {
+                            write_expanded(database, ctx, target, 1, |expanded, ctx| capability.expand().with(ctx).to(expanded))?;
+                        }
+
+                        Ok(())
+                    }
+                    None => Err(io::Error::new(io::ErrorKind::Unsupported, $unsupported_msg)),
+                }
+            }
+        }
+
+        impl Capability for $typ {
+            fn is_supported(&self, database: &Database) -> bool {
+                database.get::<$parameterised>().is_some() || database.get::<$single_step>().is_some()
+            }
+        }
+    };
+}
+
+define_relative_move!(
+    /// Moves the cursor up by `self.0` rows
+    definition: pub struct MoveUp,
+    parameterised: cap::ParmUpCursor,
+    single_step: cap::CursorUp,
+    unsupported_msg: "Moving the cursor up (terminfo cap-names 'cuu' and 'cuu1') is unsupported in this terminal",
+);
+
+define_relative_move!(
+    /// Moves the cursor down by `self.0` rows
+    definition: pub struct MoveDown,
+    parameterised: cap::ParmDownCursor,
+    single_step: cap::CursorDown,
+    unsupported_msg: "Moving the cursor down (terminfo cap-names 'cud' and 'cud1') is unsupported in this terminal",
+);
+
+define_relative_move!(
+    /// Moves the cursor left by `self.0` columns
+    definition: pub struct MoveLeft,
+    parameterised: cap::ParmLeftCursor,
+    single_step: cap::CursorLeft,
+    unsupported_msg: "Moving the cursor left (terminfo cap-names 'cub' and 'cub1') is unsupported in this terminal",
+);
+
+define_relative_move!(
+    /// Moves the cursor right by `self.0` columns
+    definition: pub struct MoveRight,
+    parameterised: cap::ParmRightCursor,
+    single_step: cap::CursorRight,
+    unsupported_msg: "Moving the cursor right (terminfo cap-names 'cuf' and 'cuf1') is unsupported in this terminal",
+);
+
+/// Moves the cursor to column `self.0` of the current row
+///
+/// Uses the 'hpa' capability if the terminal has it, and otherwise moves to the start of the line
+/// and then right.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct MoveToColumn(pub u16);
+
+impl Command for MoveToColumn {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+        if let Some(capability) = database.get::<cap::ColumnAddress>() {
+            return write_expanded(database, ctx, target, 1, |expanded, ctx| {
+                capability.expand()
+                    .x(self.0 as u32)
+                    .with(ctx)
+                    .to(expanded)
+            });
+        }
+
+        MoveToLineStart.write_to(database, ctx, target)?;
+        MoveRight(self.0).write_to(database, ctx, target)
+    }
+}
+
+impl Capability for MoveToColumn {
+    fn is_supported(&self, database: &Database) -> bool {
+        database.get::<cap::ColumnAddress>().is_some()
+            || (MoveToLineStart.is_supported(database) && MoveRight(self.0).is_supported(database))
+    }
+}
+
+/// Asks the terminal where the cursor is, which it replies to with a cursor position report
+///
+/// Uses the 'u7' capability if the terminal has it, and otherwise the standard device status
+/// report request. [Terminal::cursor_position](crate::Terminal::cursor_position) writes this and
+/// reads the reply.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RequestCursorPosition;
+
+impl Command for RequestCursorPosition {
+    fn size_hint(&self) -> Option<usize> {
+        Some(4)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        match database.get::<cap::User7>() {
+            Some(capability) => write_expanded(database, ctx, target, 1, |expanded, ctx| capability.expand().with(ctx).to(expanded)),
+            None => target.write_all(b"\x1b[6n"),
+        }
+    }
+}
+
+impl Capability for RequestCursorPosition {
+    fn is_supported(&self, database: &Database) -> bool {
+        database.get::<cap::User7>().is_some() || is_ansi_terminal(database)
+    }
+}
+
+/// Returns true if the cursor position reports described by the 'u6' capability count from 1,
+/// which they do if it uses `%i` or if the terminal doesn't have it
+pub(crate) fn position_report_is_one_based(database: &Database) -> bool {
+    match database.raw("u6") {
+        Some(terminfo::Value::String(format)) => format.windows(2).any(|window| window == b"%i"),
+        _ => true,
+    }
+}
+
+/// Parses a cursor position report (`ESC [ row ; column R`) into `(column, row)` counting from 0
+pub(crate) fn parse_position_report(reply: &[u8], one_based: bool) -> Option<(u16, u16)> {
+    let reply = std::str::from_utf8(reply.strip_prefix(b"\x1b[")?.strip_suffix(b"R")?).ok()?;
+    let (row, column) = reply.split_once(';')?;
+    let offset = u16::from(one_based);
+
+    Some((column.parse::<u16>().ok()?.saturating_sub(offset), row.parse::<u16>().ok()?.saturating_sub(offset)))
+}
+
+/// The shape of the cursor, see [SetCursorStyle]
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Underline,
+    /// A vertical line, which is usually used while inserting text
+    Bar,
+}
+
+/// Sets the shape of the cursor to `self.0`, blinking if `self.1` is true
+///
+/// The terminfo `Ss` capability is used if the database has it, otherwise the sequence xterm uses
+/// (DECSCUSR) is written. [ResetCursorStyle] or
+/// [Terminal::set_cursor_style](crate::Terminal::set_cursor_style) put back the user's cursor.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct SetCursorStyle(pub CursorShape, pub bool);
+
+impl Command for SetCursorStyle {
+    fn size_hint(&self) -> Option<usize> {
+        Some(6)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let style: u8 = match self {
+            Self(CursorShape::Block, true) => 1,
+            Self(CursorShape::Block, false) => 2,
+            Self(CursorShape::Underline, true) => 3,
+            Self(CursorShape::Underline, false) => 4,
+            Self(CursorShape::Bar, true) => 5,
+            Self(CursorShape::Bar, false) => 6,
+        };
+
+        let fallback = format!("\x1b[{style} q");
+        write_extended(database, ctx, target, "Ss", &[style.into()], fallback.as_bytes())
+    }
+}
+
+ansi_capability!(SetCursorStyle, extended: "Ss");
+
+/// Puts back the cursor shape the user has configured, using the terminfo `Se` capability if the
+/// database has it
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct ResetCursorStyle;
+
+impl Command for ResetCursorStyle {
+    fn size_hint(&self) -> Option<usize> {
+        Some(5)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write_extended(database, ctx, target, "Se", &[], b"\x1b[0 q")
+    }
+}
+
+ansi_capability!(ResetCursorStyle, extended: "Se");
+
+/// Puts back the user's cursor shape when it is dropped, see
+/// [Terminal::set_cursor_style](crate::Terminal::set_cursor_style)
+pub type CursorStyleGuard<'a, I, O> = RestoreGuard<'a, I, O, ResetCursorStyle>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_report_base() {
+        let database = |u6: Option<&[u8]>| {
+            let mut database = Database::new();
+            database.name("test");
+
+            if let Some(u6) = u6 {
+                database.raw("u6", u6);
+            }
+
+            database.build().unwrap()
+        };
+
+        assert!(position_report_is_one_based(&database(Some(b"\x1b[%i%d;%dR"))));
+        assert!(!position_report_is_one_based(&database(Some(b"\x1b[%d;%dR"))));
+
+        // xterm's reports, which count from 1, are expected without u6
+        assert!(position_report_is_one_based(&database(None)));
+    }
+
+    #[test]
+    fn position_reports() {
+        assert_eq!(parse_position_report(b"\x1b[12;40R", true), Some((39, 11)));
+        assert_eq!(parse_position_report(b"\x1b[12;40R", false), Some((40, 12)));
+
+        // a report of 0 from a terminal which should count from 1 stays at 0
+        assert_eq!(parse_position_report(b"\x1b[0;0R", true), Some((0, 0)));
+        assert_eq!(parse_position_report(b"\x1b[1;1R", true), Some((0, 0)));
+        assert_eq!(parse_position_report(b"\x1b[0;0R", false), Some((0, 0)));
+    }
+
+    #[test]
+    fn malformed_position_reports() {
+        for reply in [
+            b"\x1b[12;40".as_slice(),
+            b"[12;40R",
+            b"\x1b[12R",
+            b"\x1b[12;R",
+            b"\x1b[;40R",
+            b"\x1b[12;4xR",
+            b"\x1b[-1;40R",
+            b"\x1b[12;70000R",
+            b"\x1b[?12;40R",
+        ] {
+            assert_eq!(parse_position_report(reply, true), None, "{reply:?}");
+        }
+    }
+
+    fn written(command: impl Command, database: &Database) -> Vec<u8> {
+        let mut output = Vec::new();
+        command.write_to(database, &mut Context::new(), &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn cursor_styles_from_terminfo() {
+        let mut database = Database::new();
+        database.name("test")
+            .raw("Ss", b"\x1b[%p1%d q".as_slice())
+            .raw("Se", b"\x1b[2 q".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(SetCursorStyle(CursorShape::Bar, true).is_supported(&database));
+        assert!(ResetCursorStyle.is_supported(&database));
+
+        assert_eq!(written(SetCursorStyle(CursorShape::Block, true), &database), b"\x1b[1 q");
+        assert_eq!(written(SetCursorStyle(CursorShape::Underline, false), &database), b"\x1b[4 q");
+        assert_eq!(written(SetCursorStyle(CursorShape::Bar, true), &database), b"\x1b[5 q");
+        assert_eq!(written(ResetCursorStyle, &database), b"\x1b[2 q");
+    }
+
+    #[test]
+    fn cursor_styles_without_terminfo() {
+        let mut database = Database::new();
+        database.name("test").raw("cup", b"\x1b[%i%p1%d;%p2%dH".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(SetCursorStyle(CursorShape::Bar, false).is_supported(&database));
+        assert!(ResetCursorStyle.is_supported(&database));
+
+        assert_eq!(written(SetCursorStyle(CursorShape::Block, false), &database), b"\x1b[2 q");
+        assert_eq!(written(SetCursorStyle(CursorShape::Underline, true), &database), b"\x1b[3 q");
+        assert_eq!(written(SetCursorStyle(CursorShape::Bar, false), &database), b"\x1b[6 q");
+        assert_eq!(written(ResetCursorStyle, &database), b"\x1b[0 q");
+    }
+
+    #[test]
+    fn cursor_styles_on_other_terminals() {
+        let mut database = Database::new();
+        database.name("vt52").raw("cup", b"\x1bY%p1%' '%+%c%p2%' '%+%c".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(!SetCursorStyle(CursorShape::Block, true).is_supported(&database));
+        assert!(!ResetCursorStyle.is_supported(&database));
+    }
+}
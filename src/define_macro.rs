@@ -25,6 +25,15 @@ macro_rules! __fill_type {
     };
 }
 
+macro_rules! __fill_expr {
+    (, $default_expr:expr) => {
+        $default_expr
+    };
+    ($some_expr:expr, $default_expr:expr) => {
+        $some_expr
+    };
+}
+
 macro_rules! define {
     (default-no-args
         $(#[$attrs:meta])*
@@ -32,6 +41,7 @@ macro_rules! define {
         capability: $capability:ty,
         size_hint: $size:expr,
         unsupported_msg: $unsupported_msg:literal $(,)?
+        $(affected_lines: $affected_lines:path,)?
         $(--add-command-implementation-errors-docs $($placeholder:tt)? )?
     ) => {
         define!(custom-impl
@@ -41,27 +51,10 @@ macro_rules! define {
             size_hint: $size,
             unsupported_msg: $unsupported_msg,
             write_to_impl: |self, database, capability, ctx, target| {
+                // the number of lines proportional delays in the capability are multiplied by
+                let affected_lines: u32 = $crate::__fill_expr!($($affected_lines(database))?, 1);
 
-                match capability.expand().with(ctx).to(target) {
-                    // writing to `target` was successful
-                    Ok(_) => (),
-                    // writing to `target` was unsuccessful
-                    Err(error) => return match error {
-                        ::terminfo::Error::Parse => Err(::std::io::Error::new(
-                            ::std::io::ErrorKind::InvalidData,
-                            error
-                        )),
-                        ::terminfo::Error::NotFound => Err(::std::io::Error::new(
-                            ::std::io::ErrorKind::NotFound,
-                            error
-                        )),
-                        ::terminfo::Error::Expand(_) => Err(::std::io::Error::new(
-                            ::std::io::ErrorKind::Other,
-                            error
-                        )),
-                        ::terminfo::Error::Io(io_error) => Err(io_error)
-                    }
-                }
+                $crate::command::write_expanded(database, ctx, target, affected_lines, |expanded, ctx| capability.expand().with(ctx).to(expanded))?;
             },
             is_supported_impl: |self, database: &::terminfo::Database, capability| {
                 true
@@ -81,8 +74,8 @@ macro_rules! define {
     ) => {
 
         $(#[$attrs])*
-        #[doc = concat!(
-            $($($placeholder)?
+        $($($placeholder)?
+            #[doc = concat!(
                 "# `Command` implementation errors\n",
                 "Returns:\n",
                 "- `Err(io::Error)` with an `ErrorKind` of `Unsupported` when the terminal does not support this command\n",
@@ -90,8 +83,8 @@ macro_rules! define {
                 "- `Err(io::Error)` with an `ErrorKind` of `NotFound` when the terminfo entry for this terminal was not found\n",
                 "- `Err(io::Error)` with an `ErrorKind` of `Other` when there was an error expanding a parameterised terminfo capability.\n",
                 "May also return any other `io::Error`",
-            )?
-        )]
+            )]
+        )?
         #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
         $visible struct $typ $(( $($args)+ ))?;
 
@@ -103,7 +96,7 @@ macro_rules! define {
             fn write_to(
                 $write_to_self_var_name: &Self,
                 $write_to_database_var_name: &::terminfo::Database,
-                #[allow(unused)] $ctx_var_name: &mut ::terminfo::expand::Context,
+                #[allow(unused)] $ctx_var_name: &mut $crate::command::Context,
                 #[allow(unused)] $target_var_name: &mut dyn ::std::io::Write
             ) -> ::std::io::Result<()> {
                 match $write_to_database_var_name.get::<$capability>() {
@@ -133,6 +126,20 @@ macro_rules! define {
     };
 }
 
+/// Implements `Capability` for commands which write the sequences xterm uses, which are supported
+/// on terminals that understand ANSI escape sequences (see `command::is_ansi_terminal`). With
+/// `extended`, terminals whose database has that user defined capability support it too.
+macro_rules! ansi_capability {
+    ($typ:ty $(, extended: $capability:literal)? $(,)?) => {
+        impl $crate::Capability for $typ {
+            fn is_supported(&self, database: &$crate::terminfo::Database) -> bool {
+                $(database.raw($capability).is_some() ||)? $crate::command::is_ansi_terminal(database)
+            }
+        }
+    };
+}
+
+#[allow(unused_macros)]
 macro_rules! add_semicolon_if_unit_or_tuple_struct {
     // normal struct
     (
@@ -164,6 +171,7 @@ macro_rules! add_semicolon_if_unit_or_tuple_struct {
     }
 }
 
+#[allow(unused_macros)]
 macro_rules! new_define {
     (
         $(#[$attrs:meta])*
@@ -196,8 +204,12 @@ macro_rules! new_define {
     }
 }
 
+#[allow(unused_imports)]
+pub(crate) use new_define;
+
 pub(crate) use {
+    ansi_capability,
     define,
-    new_define,
+    __fill_expr,
     __fill_type,
 };
\ No newline at end of file
@@ -0,0 +1,1051 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! A readline style line editor
+//!
+//! [Editor::read_line] reads a line of input with Emacs style key bindings:
+//!
+//! | Keys                           | Action                                                   |
+//! |--------------------------------|----------------------------------------------------------|
+//! | Left, Ctrl-B / Right, Ctrl-F   | Move back / forward a character                          |
+//! | Ctrl-Left, Alt-B / Ctrl-Right, Alt-F | Move back / forward a word                         |
+//! | Home, Ctrl-A / End, Ctrl-E     | Move to the start / end of the line                      |
+//! | Up, Ctrl-P / Down, Ctrl-N      | Move up / down a line, or to the previous / next history entry |
+//! | Backspace, Ctrl-H / Delete, Ctrl-D | Delete the previous / next character                 |
+//! | Ctrl-K / Ctrl-U                | Kill to the end / start of the line                      |
+//! | Ctrl-W, Alt-Backspace / Alt-D  | Kill the previous / next word                            |
+//! | Ctrl-Y / Alt-Y                 | Yank the last kill / replace the yank with an older kill |
+//! | Ctrl-T                         | Swap the characters around the cursor                    |
+//! | Ctrl-R                         | Search the history incrementally                         |
+//! | Tab                            | Complete the word before the cursor                      |
+//! | Alt-Enter                      | Insert a newline                                         |
+//! | Ctrl-L                         | Clear the screen                                         |
+//! | Enter / Ctrl-C / Ctrl-D        | Accept the line / cancel / end of input (on an empty line) |
+
+use {
+    crate::{
+        cursor::{MoveToColumn, MoveToLineStart, MoveUp},
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        misc::{ClearScreen, ClearToEndOfScreen, DisableBracketedPaste, DisableKeypad, EnableBracketedPaste, EnableKeypad},
+        style::ContentStyle,
+        text::{char_width, str_width, Line, Span},
+        AsTty,
+        RawMode,
+        Terminal,
+    },
+
+    std::{
+        collections::VecDeque,
+        fs,
+        io::{self, BufRead, Write},
+        path::PathBuf,
+    },
+};
+
+/// Styles the input while it is being edited
+pub trait Highlighter {
+    /// Splits `input` into styled spans
+    ///
+    /// The contents of the returned spans joined together must be equal to `input`. `input` may
+    /// contain newlines.
+    fn highlight(&self, input: &str) -> Vec<Span>;
+}
+
+impl<F: Fn(&str) -> Vec<Span>> Highlighter for F {
+    fn highlight(&self, input: &str) -> Vec<Span> {
+        self(input)
+    }
+}
+
+/// Suggests completions for the word before the cursor when Tab is pressed
+pub trait Completer {
+    /// Returns the byte index in `input` where the word being completed starts, along with the
+    /// candidates which can replace `input[start..cursor]`
+    fn complete(&self, input: &str, cursor: usize) -> (usize, Vec<String>);
+}
+
+impl<F: Fn(&str, usize) -> (usize, Vec<String>)> Completer for F {
+    fn complete(&self, input: &str, cursor: usize) -> (usize, Vec<String>) {
+        self(input, cursor)
+    }
+}
+
+/// Previously entered lines, which can optionally be stored in a file
+///
+/// The file has one entry per line, with backslashes and newlines in entries escaped as `\\` and
+/// `\n`.
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: Vec<String>,
+    max_entries: usize,
+    path: Option<PathBuf>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    /// Creates an empty history which keeps at most 1000 entries and isn't stored in a file
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            max_entries: 1000,
+            path: None,
+        }
+    }
+
+    /// Loads the history from the file at `path`. Entries added later are appended to the file.
+    ///
+    /// The file does not need to exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut history = Self::new();
+
+        match fs::File::open(&path) {
+            Ok(file) => {
+                for line in io::BufReader::new(file).lines() {
+                    history.push(unescape(&line?));
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+            Err(error) => return Err(error),
+        }
+
+        history.path = Some(path);
+
+        Ok(history)
+    }
+
+    /// Sets the maximum number of entries kept. The oldest entries are removed first.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self.trim();
+        self
+    }
+
+    /// The entries, oldest first
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds an entry, appending it to the file if there is one
+    ///
+    /// Empty entries and entries which are the same as the last entry are ignored.
+    pub fn add(&mut self, entry: impl Into<String>) -> io::Result<()> {
+        let entry = entry.into();
+
+        if entry.trim().is_empty() || self.entries.last() == Some(&entry) {
+            return Ok(());
+        }
+
+        let trimmed = self.push(entry);
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // the whole file is rewritten when entries are removed so that it doesn't grow forever
+        if trimmed {
+            return self.save();
+        }
+
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", escape(self.entries.last().map_or("", String::as_str)))
+    }
+
+    /// Writes every entry to the file, replacing its contents. Does nothing if there is no file.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+
+        for entry in &self.entries {
+            writeln!(file, "{}", escape(entry))?;
+        }
+
+        file.flush()
+    }
+
+    /// Pushes `entry` and returns true if old entries had to be removed
+    fn push(&mut self, entry: String) -> bool {
+        self.entries.push(entry);
+        self.trim()
+    }
+
+    fn trim(&mut self) -> bool {
+        let excess = self.entries.len().saturating_sub(self.max_entries);
+        self.entries.drain(..excess);
+        excess > 0
+    }
+
+    /// Returns the index of the newest entry before `before` which contains `query`
+    fn search_backward(&self, query: &str, before: usize) -> Option<usize> {
+        self.entries[..before.min(self.entries.len())].iter().rposition(|entry| entry.contains(query))
+    }
+}
+
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            entry.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => entry.push('\n'),
+            Some(other) => entry.push(other),
+            None => entry.push('\\'),
+        }
+    }
+
+    entry
+}
+
+/// The most recently killed text, newest first
+#[derive(Debug, Clone, Default)]
+struct KillRing {
+    entries: VecDeque<String>,
+}
+
+impl KillRing {
+    const MAX_ENTRIES: usize = 16;
+
+    /// Adds killed text. If `append` is true, the text is joined onto the newest entry, before it
+    /// if the text was killed backwards.
+    fn kill(&mut self, text: &str, append: bool, backwards: bool) {
+        match self.entries.front_mut() {
+            Some(newest) if append => match backwards {
+                true => newest.insert_str(0, text),
+                false => newest.push_str(text),
+            },
+            _ => {
+                self.entries.push_front(text.to_owned());
+                self.entries.truncate(Self::MAX_ENTRIES);
+            }
+        }
+    }
+
+    fn newest(&self) -> Option<&str> {
+        self.entries.front().map(String::as_str)
+    }
+
+    /// Moves the newest entry to the back and returns the new newest entry
+    fn rotate(&mut self) -> Option<&str> {
+        self.entries.rotate_left(1.min(self.entries.len()));
+        self.newest()
+    }
+}
+
+/// A readline style line editor
+///
+/// The same editor should be used for every line read so that the history and the kill ring are
+/// kept between lines.
+#[derive(Default)]
+pub struct Editor {
+    history: History,
+    kill_ring: KillRing,
+    highlighter: Option<Box<dyn Highlighter>>,
+    completer: Option<Box<dyn Completer>>,
+    /// the error from storing the last accepted line in the history file
+    history_error: Option<io::Error>,
+}
+
+/// What the previous key did, which changes what some keys do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LastAction {
+    Other,
+    /// text was killed, so further kills are joined to it
+    Kill,
+    /// text was yanked into `start..end`, so it can be replaced with an older kill
+    Yank { start: usize, end: usize },
+}
+
+enum Mode {
+    Normal,
+    Search(Search),
+    Menu(Menu),
+}
+
+/// An incremental reverse search through the history
+struct Search {
+    query: String,
+    /// the index of the history entry that matched
+    found: Option<usize>,
+    failed: bool,
+    original: (String, usize),
+}
+
+/// A menu of completion candidates shown below the input
+struct Menu {
+    start: usize,
+    candidates: Vec<String>,
+    selected: Option<usize>,
+    /// the word that was being completed before any candidate was selected
+    original: String,
+}
+
+enum Flow {
+    Continue,
+    Accept,
+    EndOfInput,
+    Cancel,
+}
+
+/// The state of the line currently being edited
+struct State<'a> {
+    prompt: &'a str,
+    buffer: String,
+    /// a byte index into `buffer`
+    cursor: usize,
+    /// the row the cursor was left on by the last render, relative to the first row of the prompt
+    cursor_row: usize,
+    mode: Mode,
+    last_action: LastAction,
+    /// the history entry being shown, if the history has been moved through
+    history_index: Option<usize>,
+    /// what was being edited before moving through the history
+    saved_buffer: String,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_highlighter(mut self, highlighter: impl Highlighter + 'static) -> Self {
+        self.highlighter = Some(Box::new(highlighter));
+        self
+    }
+
+    pub fn with_completer(mut self, completer: impl Completer + 'static) -> Self {
+        self.completer = Some(Box::new(completer));
+        self
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    /// Returns the error from appending the last accepted line to the history file, if there was
+    /// one, and clears it
+    ///
+    /// [read_line](Self::read_line) returns the line even if it couldn't be stored.
+    pub fn take_history_error(&mut self) -> Option<io::Error> {
+        self.history_error.take()
+    }
+
+    /// Reads a line of input after writing `prompt`
+    ///
+    /// Raw mode and bracketed paste are enabled while the line is being edited, so pasted text is
+    /// inserted as it is rather than being treated as key presses. The accepted line is added to
+    /// the history, and if writing it to the history file fails the error is kept for
+    /// [take_history_error](Self::take_history_error).
+    ///
+    /// Returns `Ok(None)` if Ctrl-D is pressed on an empty line or the input ends, and an error with
+    /// an `ErrorKind` of `Interrupted` if Ctrl-C is pressed.
+    pub fn read_line<I: io::Read + AsTty, O: io::Write>(&mut self, terminal: &mut Terminal<I, O>, prompt: &str) -> io::Result<Option<String>> {
+
+        let raw_mode = RawMode::enable(terminal.reader())?;
+        terminal.queue_if_supported(EnableKeypad).transpose()?;
+        terminal.queue_if_supported(EnableBracketedPaste).transpose()?;
+
+        let mut state = State {
+            prompt,
+            buffer: String::new(),
+            cursor: 0,
+            cursor_row: 0,
+            mode: Mode::Normal,
+            last_action: LastAction::Other,
+            history_index: None,
+            saved_buffer: String::new(),
+        };
+
+        let result = self.edit(terminal, &mut state);
+
+        // leave the cursor on the line after the input
+        state.mode = Mode::Normal;
+        state.cursor = state.buffer.len();
+        let rendered = self.render(terminal, &mut state).and_then(|()| terminal.write_all(b"\r\n"));
+
+        // the terminal is restored even if the line couldn't be drawn
+        let restored = restore(terminal);
+        let disabled = raw_mode.disable();
+
+        // an error from editing is more useful than one from restoring the terminal afterwards
+        let result = result.and_then(|flow| rendered.and(restored).and(disabled).map(|()| flow));
+
+        match result {
+            Ok(Flow::Accept) => {
+                self.history_error = self.history.add(state.buffer.clone()).err();
+                Ok(Some(state.buffer))
+            }
+            Ok(Flow::Cancel) => Err(io::Error::new(io::ErrorKind::Interrupted, "the line was cancelled")),
+            Ok(_) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn edit<I: io::Read, O: io::Write>(&mut self, terminal: &mut Terminal<I, O>, state: &mut State) -> io::Result<Flow> {
+
+        self.render(terminal, state)?;
+
+        loop {
+            let key = match terminal.read_event() {
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+                // pasted text is inserted as it is, ending any search or completion
+                Ok(Event::Paste(text)) => {
+                    state.mode = Mode::Normal;
+                    state.last_action = LastAction::Other;
+                    state.insert(&text);
+                    self.render(terminal, state)?;
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(match state.buffer.is_empty() {
+                        true => Flow::EndOfInput,
+                        false => Flow::Accept,
+                    });
+                }
+                Err(error) => return Err(error),
+            };
+
+            let flow = match std::mem::replace(&mut state.mode, Mode::Normal) {
+                Mode::Normal => self.handle_key(terminal, state, key)?,
+                Mode::Search(search) => self.handle_search_key(terminal, state, search, key)?,
+                Mode::Menu(menu) => self.handle_menu_key(terminal, state, menu, key)?,
+            };
+
+            match flow {
+                Flow::Continue => self.render(terminal, state)?,
+                flow => return Ok(flow),
+            }
+        }
+    }
+
+    fn handle_key<I: io::Read, O: io::Write>(&mut self, terminal: &mut Terminal<I, O>, state: &mut State, key: KeyEvent) -> io::Result<Flow> {
+
+        const NONE: KeyModifiers = KeyModifiers::NONE;
+        const SHIFT: KeyModifiers = KeyModifiers::SHIFT;
+        const CONTROL: KeyModifiers = KeyModifiers::CONTROL;
+        const ALT: KeyModifiers = KeyModifiers::ALT;
+
+        let last_action = std::mem::replace(&mut state.last_action, LastAction::Other);
+
+        match (key.code, key.modifiers) {
+            (KeyCode::Enter, NONE) | (KeyCode::Char('j' | 'm'), CONTROL) => return Ok(Flow::Accept),
+            (KeyCode::Char('c'), CONTROL) => return Ok(Flow::Cancel),
+            (KeyCode::Char('d'), CONTROL) if state.buffer.is_empty() => return Ok(Flow::EndOfInput),
+
+            (KeyCode::Enter, ALT) => state.insert("\n"),
+            (KeyCode::Char(c), NONE | SHIFT) => state.insert(c.encode_utf8(&mut [0; 4])),
+
+            (KeyCode::Left, NONE) | (KeyCode::Char('b'), CONTROL) => state.cursor = state.previous_char(),
+            (KeyCode::Right, NONE) | (KeyCode::Char('f'), CONTROL) => state.cursor = state.next_char(),
+            (KeyCode::Left, CONTROL) | (KeyCode::Char('b'), ALT) => state.cursor = state.previous_word(is_word_char),
+            (KeyCode::Right, CONTROL) | (KeyCode::Char('f'), ALT) => state.cursor = state.next_word(),
+            (KeyCode::Home, _) | (KeyCode::Char('a'), CONTROL) => state.cursor = state.line_start(),
+            (KeyCode::End, _) | (KeyCode::Char('e'), CONTROL) => state.cursor = state.line_end(),
+
+            (KeyCode::Backspace, NONE) | (KeyCode::Char('h'), CONTROL) => {
+                let start = state.previous_char();
+                state.buffer.drain(start..state.cursor);
+                state.cursor = start;
+            }
+            (KeyCode::Delete, NONE) | (KeyCode::Char('d'), CONTROL) => {
+                let end = state.next_char();
+                state.buffer.drain(state.cursor..end);
+            }
+
+            (KeyCode::Char('k'), CONTROL) => {
+                // at the end of a line, the newline is killed instead
+                let end = match state.line_end() {
+                    end if end == state.cursor => state.next_char(),
+                    end => end,
+                };
+                self.kill(state, state.cursor..end, last_action, false);
+            }
+            (KeyCode::Char('u'), CONTROL) => self.kill(state, state.line_start()..state.cursor, last_action, true),
+            (KeyCode::Char('w'), CONTROL) => {
+                let start = state.previous_word(|c| !c.is_whitespace());
+                self.kill(state, start..state.cursor, last_action, true);
+            }
+            (KeyCode::Backspace, ALT) => self.kill(state, state.previous_word(is_word_char)..state.cursor, last_action, true),
+            (KeyCode::Char('d'), ALT) => self.kill(state, state.cursor..state.next_word(), last_action, false),
+
+            (KeyCode::Char('y'), CONTROL) => {
+                if let Some(text) = self.kill_ring.newest() {
+                    let start = state.cursor;
+                    state.insert(text);
+                    state.last_action = LastAction::Yank { start, end: state.cursor };
+                }
+            }
+            (KeyCode::Char('y'), ALT) => {
+                if let LastAction::Yank { start, end } = last_action && let Some(text) = self.kill_ring.rotate() {
+                    state.buffer.replace_range(start..end, text);
+                    state.cursor = start + text.len();
+                    state.last_action = LastAction::Yank { start, end: state.cursor };
+                }
+            }
+
+            (KeyCode::Char('t'), CONTROL) => state.transpose(),
+
+            (KeyCode::Up, NONE) | (KeyCode::Char('p'), CONTROL) => match state.line_start() {
+                0 => self.history_previous(state),
+                start => state.cursor = state.column_in_line(start - 1, state.cursor - start),
+            },
+            (KeyCode::Down, NONE) | (KeyCode::Char('n'), CONTROL) => match state.line_end() {
+                end if end == state.buffer.len() => self.history_next(state),
+                end => state.cursor = state.column_in_line(end + 1, state.cursor - state.line_start()),
+            },
+
+            (KeyCode::Char('r'), CONTROL) => {
+                state.mode = Mode::Search(Search {
+                    query: String::new(),
+                    found: None,
+                    failed: false,
+                    original: (state.buffer.clone(), state.cursor),
+                });
+            }
+
+            (KeyCode::Tab, NONE) => self.complete(state),
+
+            (KeyCode::Char('l'), CONTROL) => {
+                terminal.queue_if_supported(ClearScreen).transpose()?;
+                state.cursor_row = 0;
+            }
+
+            _ => state.last_action = last_action,
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn handle_search_key<I: io::Read, O: io::Write>(&mut self, terminal: &mut Terminal<I, O>, state: &mut State, mut search: Search, key: KeyEvent) -> io::Result<Flow> {
+
+        let search_from = match (key.code, key.modifiers) {
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => search.found.unwrap_or(self.history.len()),
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                search.query.push(c);
+                self.history.len()
+            }
+            (KeyCode::Backspace, KeyModifiers::NONE) => {
+                search.query.pop();
+                self.history.len()
+            }
+            (KeyCode::Esc, _) | (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+                (state.buffer, state.cursor) = search.original;
+                return Ok(Flow::Continue);
+            }
+            // any other key ends the search, keeping the entry that was found, and is then handled
+            // as normal
+            _ => return self.handle_key(terminal, state, key),
+        };
+
+        match self.history.search_backward(&search.query, search_from) {
+            Some(index) if !search.query.is_empty() => {
+                let entry = &self.history.entries()[index];
+                state.cursor = entry.find(&search.query).unwrap_or(0);
+                state.buffer = entry.clone();
+                state.history_index = Some(index);
+                search.found = Some(index);
+                search.failed = false;
+            }
+            _ => search.failed = !search.query.is_empty(),
+        }
+
+        state.mode = Mode::Search(search);
+
+        Ok(Flow::Continue)
+    }
+
+    fn handle_menu_key<I: io::Read, O: io::Write>(&mut self, terminal: &mut Terminal<I, O>, state: &mut State, mut menu: Menu, key: KeyEvent) -> io::Result<Flow> {
+
+        let count = menu.candidates.len();
+
+        let selected = match key.code {
+            KeyCode::Tab | KeyCode::Down => menu.selected.map_or(0, |selected| (selected + 1) % count),
+            KeyCode::BackTab | KeyCode::Up => menu.selected.map_or(count - 1, |selected| (selected + count - 1) % count),
+            KeyCode::Enter if menu.selected.is_some() => return Ok(Flow::Continue),
+            KeyCode::Esc => {
+                state.buffer.replace_range(menu.start..state.cursor, &menu.original);
+                state.cursor = menu.start + menu.original.len();
+                return Ok(Flow::Continue);
+            }
+            _ => return self.handle_key(terminal, state, key),
+        };
+
+        state.buffer.replace_range(menu.start..state.cursor, &menu.candidates[selected]);
+        state.cursor = menu.start + menu.candidates[selected].len();
+        menu.selected = Some(selected);
+        state.mode = Mode::Menu(menu);
+
+        Ok(Flow::Continue)
+    }
+
+    fn kill(&mut self, state: &mut State, range: std::ops::Range<usize>, last_action: LastAction, backwards: bool) {
+        if range.is_empty() {
+            return;
+        }
+
+        let start = range.start;
+        let text: String = state.buffer.drain(range).collect();
+        self.kill_ring.kill(&text, last_action == LastAction::Kill, backwards);
+        state.cursor = start;
+        state.last_action = LastAction::Kill;
+    }
+
+    fn history_previous(&mut self, state: &mut State) {
+        let index = state.history_index.unwrap_or(self.history.len());
+
+        if index == 0 {
+            return;
+        }
+
+        if state.history_index.is_none() {
+            state.saved_buffer = std::mem::take(&mut state.buffer);
+        }
+
+        state.history_index = Some(index - 1);
+        state.buffer = self.history.entries()[index - 1].clone();
+        state.cursor = state.buffer.len();
+    }
+
+    fn history_next(&mut self, state: &mut State) {
+        match state.history_index {
+            None => return,
+            Some(index) if index + 1 < self.history.len() => {
+                state.history_index = Some(index + 1);
+                state.buffer = self.history.entries()[index + 1].clone();
+            }
+            Some(_) => {
+                state.history_index = None;
+                state.buffer = std::mem::take(&mut state.saved_buffer);
+            }
+        }
+
+        state.cursor = state.buffer.len();
+    }
+
+    fn complete(&mut self, state: &mut State) {
+        let Some(completer) = &self.completer else {
+            return;
+        };
+
+        let (start, candidates) = completer.complete(&state.buffer, state.cursor);
+
+        if start > state.cursor || !state.buffer.is_char_boundary(start) {
+            return;
+        }
+
+        let word = &state.buffer[start..state.cursor];
+
+        match candidates.as_slice() {
+            [] => (),
+            [only] => {
+                state.buffer.replace_range(start..state.cursor, only);
+                state.cursor = start + only.len();
+            }
+            [first, rest @ ..] => {
+                let prefix = rest.iter().fold(first.as_str(), |prefix, candidate| common_prefix(prefix, candidate));
+
+                if prefix.len() > word.len() && prefix.starts_with(word) {
+                    let prefix = prefix.to_owned();
+                    state.buffer.replace_range(start..state.cursor, &prefix);
+                    state.cursor = start + prefix.len();
+                } else {
+                    state.mode = Mode::Menu(Menu {
+                        start,
+                        original: word.to_owned(),
+                        candidates,
+                        selected: None,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Redraws the prompt, the input and the completion menu, and moves the cursor to where it is
+    /// in the input
+    fn render<I: io::Read, O: io::Write>(&self, terminal: &mut Terminal<I, O>, state: &mut State) -> io::Result<()> {
+
+        let width = terminal.size().map_or(80, |(columns, _)| columns as usize).max(1);
+
+        let prompt = match &state.mode {
+            Mode::Search(search) if search.failed => format!("(failed reverse-i-search)`{}': ", search.query),
+            Mode::Search(search) => format!("(reverse-i-search)`{}': ", search.query),
+            _ => state.prompt.to_owned(),
+        };
+
+        let spans = match &self.highlighter {
+            Some(highlighter) => highlighter.highlight(&state.buffer),
+            None => vec![Span::plain(state.buffer.as_str())],
+        };
+
+        // continuation lines are indented to line up with the first line
+        let indent = " ".repeat(str_width(&prompt));
+        let mut lines = vec![Line::from(Span::plain(prompt.as_str()))];
+
+        for span in spans {
+            for (index, part) in span.content.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(Line::from(Span::plain(indent.as_str())));
+                }
+
+                if let Some(line) = lines.last_mut() {
+                    line.push(Span::new(part, span.style));
+                }
+            }
+        }
+
+        if let Mode::Menu(menu) = &state.mode {
+            // the candidates shown are scrolled so that the selected one is always visible
+            let first = menu.selected.unwrap_or(0).saturating_sub(MENU_HEIGHT - 1);
+
+            for (index, candidate) in menu.candidates.iter().enumerate().skip(first).take(MENU_HEIGHT) {
+                let style = match menu.selected == Some(index) {
+                    true => ContentStyle::new().reverse(),
+                    false => ContentStyle::new(),
+                };
+                // the menu is kept to one row per candidate
+                let truncated: String = candidate.chars()
+                    .scan(0, |used, c| {
+                        *used += char_width(c);
+                        (*used < width).then_some(c)
+                    })
+                    .collect();
+
+                lines.push(Line::from(Span::new(truncated, style)));
+            }
+        }
+
+        // where the cursor should end up
+        let (mut cursor_row, mut cursor_column) = advance((0, 0), &prompt, width);
+        for (index, part) in state.buffer[..state.cursor].split('\n').enumerate() {
+            if index > 0 {
+                (cursor_row, cursor_column) = advance((cursor_row + 1, 0), &indent, width);
+            }
+            (cursor_row, cursor_column) = advance((cursor_row, cursor_column), part, width);
+        }
+        // the cursor can't be past the last column, so it goes at the start of the next row
+        if cursor_column >= width {
+            (cursor_row, cursor_column) = (cursor_row + 1, 0);
+        }
+
+        terminal.queue_if_supported(MoveUp(state.cursor_row as u16)).transpose()?;
+        terminal.queue_if_supported(MoveToLineStart).transpose()?;
+        terminal.queue_if_supported(ClearToEndOfScreen).transpose()?;
+
+        let mut end = (0, 0);
+
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                terminal.write_all(b"\r\n")?;
+                end = (end.0 + 1, 0);
+            }
+
+            end = line.spans.iter().fold(end, |position, span| advance(position, &span.content, width));
+            terminal.queue(line)?;
+        }
+
+        if end.0 < cursor_row {
+            terminal.write_all(b"\r\n")?;
+            end = (end.0 + 1, 0);
+        }
+
+        terminal.queue_if_supported(MoveUp((end.0 - cursor_row) as u16)).transpose()?;
+        terminal.queue_if_supported(MoveToColumn(cursor_column as u16)).transpose()?;
+
+        state.cursor_row = cursor_row;
+
+        terminal.flush()
+    }
+}
+
+/// Turns off the modes [Editor::read_line] turned on, even if drawing the line failed
+fn restore<I: io::Read, O: io::Write>(terminal: &mut Terminal<I, O>) -> io::Result<()> {
+    let keypad = terminal.queue_if_supported(DisableKeypad).transpose();
+    let paste = terminal.queue_if_supported(DisableBracketedPaste).transpose();
+    let flushed = terminal.flush();
+
+    keypad.and(paste).and(flushed)
+}
+
+/// The most completion candidates shown at once
+const MENU_HEIGHT: usize = 8;
+
+/// Returns the (row, column) the cursor is at after writing `text` at `position`, in a terminal
+/// `width` columns wide. A column equal to `width` means the row has been filled.
+fn advance((mut row, mut column): (usize, usize), text: &str, width: usize) -> (usize, usize) {
+    for c in text.chars() {
+        let char_width = char_width(c);
+
+        if column + char_width > width {
+            row += 1;
+            column = 0;
+        }
+
+        column += char_width;
+    }
+
+    (row, column)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let length = a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index);
+
+    &a[..length]
+}
+
+impl State<'_> {
+    fn insert(&mut self, text: &str) {
+        self.buffer.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    fn previous_char(&self) -> usize {
+        self.buffer[..self.cursor].char_indices().next_back().map_or(0, |(index, _)| index)
+    }
+
+    fn next_char(&self) -> usize {
+        self.buffer[self.cursor..].chars().next().map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// The start of the word before the cursor, where words are made of characters for which
+    /// `is_word` returns true
+    fn previous_word(&self, is_word: impl Fn(char) -> bool) -> usize {
+        let before = &self.buffer[..self.cursor];
+        let end_of_word = before.trim_end_matches(|c| !is_word(c));
+        end_of_word.trim_end_matches(is_word).len()
+    }
+
+    /// The end of the word after the cursor
+    fn next_word(&self) -> usize {
+        let after = &self.buffer[self.cursor..];
+        let start_of_word = after.trim_start_matches(|c| !is_word_char(c));
+        self.buffer.len() - start_of_word.trim_start_matches(is_word_char).len()
+    }
+
+    /// The start of the line the cursor is on
+    fn line_start(&self) -> usize {
+        self.buffer[..self.cursor].rfind('\n').map_or(0, |index| index + 1)
+    }
+
+    /// The end of the line the cursor is on
+    fn line_end(&self) -> usize {
+        self.buffer[self.cursor..].find('\n').map_or(self.buffer.len(), |index| self.cursor + index)
+    }
+
+    /// Returns the index of the character `column` bytes into the line containing `index`, or the
+    /// end of that line if it is shorter
+    fn column_in_line(&self, index: usize, column: usize) -> usize {
+        let start = self.buffer[..index].rfind('\n').map_or(0, |index| index + 1);
+        let end = self.buffer[start..].find('\n').map_or(self.buffer.len(), |index| start + index);
+        let mut target = (start + column).min(end);
+
+        while !self.buffer.is_char_boundary(target) {
+            target -= 1;
+        }
+
+        target
+    }
+
+    /// Swaps the characters before and after the cursor, or the two characters before the cursor
+    /// at the end of the line
+    fn transpose(&mut self) {
+        let mut cursor = self.cursor;
+
+        if cursor == self.line_end() {
+            cursor = self.previous_char();
+        }
+
+        let before = self.buffer[..cursor].chars().next_back();
+        let after = self.buffer[cursor..].chars().next();
+
+        if let (Some(before), Some(after)) = (before, after) && before != '\n' && after != '\n' {
+            let start = cursor - before.len_utf8();
+            let end = cursor + after.len_utf8();
+            self.buffer.replace_range(start..end, &format!("{after}{before}"));
+            self.cursor = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A state editing `buffer` with the cursor at the `|` in it
+    fn editing(buffer: &str) -> State<'static> {
+        let cursor = buffer.find('|').expect("the buffer has a cursor");
+
+        State {
+            prompt: "",
+            buffer: buffer.replacen('|', "", 1),
+            cursor,
+            cursor_row: 0,
+            mode: Mode::Normal,
+            last_action: LastAction::Other,
+            history_index: None,
+            saved_buffer: String::new(),
+        }
+    }
+
+    #[test]
+    fn history_escaping_round_trips() {
+        for entry in ["plain", "two\nlines", "back\\slash", "\\n literally", "ends with \\"] {
+            let escaped = escape(entry);
+            assert!(!escaped.contains('\n'));
+            assert_eq!(unescape(&escaped), entry);
+        }
+
+        assert_eq!(escape("a\\b\nc"), "a\\\\b\\nc");
+        // a backslash at the end of a line, which escape never writes, is kept
+        assert_eq!(unescape("a\\"), "a\\");
+    }
+
+    #[test]
+    fn history_ignores_blank_and_repeated_entries() {
+        let mut history = History::new().with_max_entries(2);
+
+        for entry in ["one", " ", "one", "two", "three"] {
+            history.add(entry).unwrap();
+        }
+
+        assert_eq!(history.entries(), ["two", "three"]);
+        assert_eq!(history.search_backward("t", 2), Some(1));
+        assert_eq!(history.search_backward("t", 1), Some(0));
+        assert_eq!(history.search_backward("one", 2), None);
+    }
+
+    #[test]
+    fn kill_ring_appends_consecutive_kills() {
+        let mut ring = KillRing::default();
+
+        ring.kill("world", false, false);
+        ring.kill("!", true, false);
+        ring.kill("hello ", true, true);
+        assert_eq!(ring.newest(), Some("hello world!"));
+
+        ring.kill("other", false, false);
+        assert_eq!(ring.newest(), Some("other"));
+        assert_eq!(ring.rotate(), Some("hello world!"));
+        assert_eq!(ring.rotate(), Some("other"));
+    }
+
+    #[test]
+    fn kill_ring_keeps_the_newest_entries() {
+        let mut ring = KillRing::default();
+
+        for index in 0..KillRing::MAX_ENTRIES + 4 {
+            ring.kill(&index.to_string(), false, false);
+        }
+
+        assert_eq!(ring.entries.len(), KillRing::MAX_ENTRIES);
+        assert_eq!(ring.newest(), Some("19"));
+        assert_eq!(ring.entries.back().map(String::as_str), Some("4"));
+        assert_eq!(KillRing::default().rotate(), None);
+    }
+
+    #[test]
+    fn word_motion() {
+        let state = editing("foo_bar, baz|.qux");
+        assert_eq!(state.previous_word(is_word_char), "foo_bar, ".len());
+        assert_eq!(state.next_word(), "foo_bar, baz.qux".len());
+
+        let state = editing("foo_bar, |baz");
+        assert_eq!(state.previous_word(is_word_char), 0);
+        assert_eq!(state.previous_word(|c| !c.is_whitespace()), 0);
+        assert_eq!(state.next_word(), "foo_bar, baz".len());
+
+        let state = editing("héllo wörld|");
+        assert_eq!(state.previous_word(is_word_char), "héllo ".len());
+        assert_eq!(state.previous_char(), "héllo wörl".len());
+    }
+
+    #[test]
+    fn lines_and_columns() {
+        let state = editing("first\nsec|ond\nthird");
+        assert_eq!(state.line_start(), "first\n".len());
+        assert_eq!(state.line_end(), "first\nsecond".len());
+        // moving up keeps the column, and stops at the end of a shorter line
+        assert_eq!(state.column_in_line(0, 3), 3);
+        assert_eq!(state.column_in_line("first\nsecond\n".len(), 9), "first\nsecond\nthird".len());
+    }
+
+    #[test]
+    fn transpose() {
+        let mut state = editing("ab|cd");
+        state.transpose();
+        assert_eq!((state.buffer.as_str(), state.cursor), ("acbd", 3));
+
+        // at the end of the line, the two characters before the cursor are swapped
+        let mut state = editing("abc|\ndef");
+        state.transpose();
+        assert_eq!((state.buffer.as_str(), state.cursor), ("acb\ndef", 3));
+
+        let mut state = editing("aé|");
+        state.transpose();
+        assert_eq!((state.buffer.as_str(), state.cursor), ("éa", "éa".len()));
+
+        // nothing is swapped across a newline or at the start
+        let mut state = editing("a\n|b");
+        state.transpose();
+        assert_eq!(state.buffer, "a\nb");
+
+        let mut state = editing("|ab");
+        state.transpose();
+        assert_eq!(state.buffer, "ab");
+    }
+}
@@ -0,0 +1,947 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Input events, such as key presses, read with [Terminal::read_event](crate::Terminal::read_event)
+//!
+//! The terminal should be in [raw mode](crate::RawMode) while reading events, otherwise input is
+//! only received a line at a time. Key sequences are decoded using the key capabilities in the
+//! terminfo database, so [EnableKeypad](crate::misc::EnableKeypad) should be queued first, and
+//! the common ANSI sequences are understood as well.
+
+use terminfo::Database;
+
+/// Something that happened in the terminal
+#[non_exhaustive]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Event {
+    Key(KeyEvent),
+    /// Text pasted while [bracketed paste](crate::misc::EnableBracketedPaste) is enabled. Line
+    /// breaks are converted to `\n`, and text past the
+    /// [paste limit](crate::Terminal::set_paste_limit) is dropped.
+    Paste(String),
+    /// The terminal window gained focus, while [focus reporting](crate::misc::EnableFocusChange)
+    /// is enabled
+    FocusGained,
+    /// The terminal window lost focus
+    FocusLost,
+    /// The reply to [QueryKeyboardEnhancementFlags](crate::misc::QueryKeyboardEnhancementFlags),
+    /// with the flags currently enabled. Terminals which don't support the kitty keyboard protocol
+    /// don't reply.
+    KeyboardEnhancementFlags(KeyboardEnhancementFlags),
+}
+
+/// A key being pressed along with the modifier keys that were held
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    /// Whether the key was pressed, repeated or released. Only terminals using the kitty keyboard
+    /// protocol with [KeyboardEnhancementFlags::REPORT_EVENT_TYPES] report repeats and releases.
+    pub kind: KeyEventKind,
+}
+
+impl KeyEvent {
+    /// Creates a key press event
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+        }
+    }
+
+    pub const fn with_kind(mut self, kind: KeyEventKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum KeyEventKind {
+    #[default]
+    Press,
+    /// The key was held down long enough to repeat
+    Repeat,
+    Release,
+}
+
+impl KeyEventKind {
+    /// Decodes the event type sent in kitty keyboard protocol sequences, such as the 3 in
+    /// `CSI 97;1:3u` (a being released)
+    const fn from_kitty_parameter(parameter: u32) -> Self {
+        match parameter {
+            2 => Self::Repeat,
+            3 => Self::Release,
+            _ => Self::Press,
+        }
+    }
+}
+
+impl From<KeyCode> for KeyEvent {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum KeyCode {
+    /// A character key. Letters are lowercase when control is held, as terminals can't tell
+    /// whether shift was held as well, unless the kitty keyboard protocol is being used.
+    Char(char),
+    Enter,
+    Tab,
+    /// Shift + Tab
+    BackTab,
+    Backspace,
+    Esc,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// A function key, such as `F(1)` for F1
+    F(u8),
+}
+
+/// The modifier keys held during a key event, which can be combined with `|`
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct KeyModifiers(u8);
+
+impl KeyModifiers {
+    pub const NONE: Self = Self(0);
+    pub const SHIFT: Self = Self(1 << 0);
+    pub const ALT: Self = Self(1 << 1);
+    pub const CONTROL: Self = Self(1 << 2);
+    /// The super (windows or command) key, which is only reported with the kitty keyboard protocol
+    pub const SUPER: Self = Self(1 << 3);
+    /// Only reported with the kitty keyboard protocol
+    pub const HYPER: Self = Self(1 << 4);
+    /// Only reported with the kitty keyboard protocol. Otherwise, meta is reported as alt.
+    pub const META: Self = Self(1 << 5);
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every modifier in `other` is also in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Decodes the modifier parameter used in xterm style sequences, such as the 5 in `CSI 1;5A`
+    /// (Control + Up)
+    const fn from_xterm_parameter(parameter: u32) -> Self {
+        let bits = parameter.saturating_sub(1);
+        let mut modifiers = 0;
+
+        if bits & 1 != 0 {
+            modifiers |= Self::SHIFT.0;
+        }
+        // meta is treated the same as alt
+        if bits & (2 | 8) != 0 {
+            modifiers |= Self::ALT.0;
+        }
+        if bits & 4 != 0 {
+            modifiers |= Self::CONTROL.0;
+        }
+
+        Self(modifiers)
+    }
+
+    /// Decodes the modifier parameter used in kitty keyboard protocol sequences, which has a bit
+    /// for each of shift, alt, control, super, hyper and meta (in the same order as the bits of
+    /// `KeyModifiers`), followed by bits for caps lock and num lock which are ignored
+    const fn from_kitty_parameter(parameter: u32) -> Self {
+        Self((parameter.saturating_sub(1) & 0x3F) as u8)
+    }
+}
+
+impl std::ops::BitOr for KeyModifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for KeyModifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The kitty keyboard protocol features to enable, which can be combined with `|`
+///
+/// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/> for what each flag does.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct KeyboardEnhancementFlags(u8);
+
+impl KeyboardEnhancementFlags {
+    /// Keys which are ambiguous in the legacy encoding, such as Ctrl-I and Tab, or Esc and the
+    /// start of an escape sequence, are sent as unambiguous sequences
+    pub const DISAMBIGUATE_ESCAPE_CODES: Self = Self(1 << 0);
+    /// Key repeats and releases are reported (see [KeyEventKind])
+    pub const REPORT_EVENT_TYPES: Self = Self(1 << 1);
+    /// The shifted version of keys is reported, so shift + a is reported as `A`
+    pub const REPORT_ALTERNATE_KEYS: Self = Self(1 << 2);
+    /// Every key, including Enter, Tab and Backspace, is sent as an escape sequence
+    pub const REPORT_ALL_KEYS_AS_ESCAPE_CODES: Self = Self(1 << 3);
+    pub const REPORT_ASSOCIATED_TEXT: Self = Self(1 << 4);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & 0x1F)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every flag in `other` is also in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for KeyboardEnhancementFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for KeyboardEnhancementFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+const ESC: u8 = 0x1B;
+
+/// The most bytes of pasted text kept by default
+pub(crate) const DEFAULT_PASTE_LIMIT: usize = 1 << 20;
+
+/// The terminfo key capabilities that are decoded, and the keys they represent
+const KEY_CAPABILITIES: &[(&str, KeyCode, KeyModifiers)] = &[
+    ("key_up", KeyCode::Up, KeyModifiers::NONE),
+    ("key_down", KeyCode::Down, KeyModifiers::NONE),
+    ("key_left", KeyCode::Left, KeyModifiers::NONE),
+    ("key_right", KeyCode::Right, KeyModifiers::NONE),
+    ("key_home", KeyCode::Home, KeyModifiers::NONE),
+    ("key_end", KeyCode::End, KeyModifiers::NONE),
+    ("key_ppage", KeyCode::PageUp, KeyModifiers::NONE),
+    ("key_npage", KeyCode::PageDown, KeyModifiers::NONE),
+    ("key_ic", KeyCode::Insert, KeyModifiers::NONE),
+    ("key_dc", KeyCode::Delete, KeyModifiers::NONE),
+    ("key_backspace", KeyCode::Backspace, KeyModifiers::NONE),
+    ("key_enter", KeyCode::Enter, KeyModifiers::NONE),
+    ("key_btab", KeyCode::BackTab, KeyModifiers::SHIFT),
+    ("key_sleft", KeyCode::Left, KeyModifiers::SHIFT),
+    ("key_sright", KeyCode::Right, KeyModifiers::SHIFT),
+    ("key_shome", KeyCode::Home, KeyModifiers::SHIFT),
+    ("key_send", KeyCode::End, KeyModifiers::SHIFT),
+    ("key_sdc", KeyCode::Delete, KeyModifiers::SHIFT),
+    ("key_f1", KeyCode::F(1), KeyModifiers::NONE),
+    ("key_f2", KeyCode::F(2), KeyModifiers::NONE),
+    ("key_f3", KeyCode::F(3), KeyModifiers::NONE),
+    ("key_f4", KeyCode::F(4), KeyModifiers::NONE),
+    ("key_f5", KeyCode::F(5), KeyModifiers::NONE),
+    ("key_f6", KeyCode::F(6), KeyModifiers::NONE),
+    ("key_f7", KeyCode::F(7), KeyModifiers::NONE),
+    ("key_f8", KeyCode::F(8), KeyModifiers::NONE),
+    ("key_f9", KeyCode::F(9), KeyModifiers::NONE),
+    ("key_f10", KeyCode::F(10), KeyModifiers::NONE),
+    ("key_f11", KeyCode::F(11), KeyModifiers::NONE),
+    ("key_f12", KeyCode::F(12), KeyModifiers::NONE),
+];
+
+/// The result of trying to decode the start of the input
+enum Decoded {
+    /// An event was decoded from the first `usize` bytes
+    Event(Event, usize),
+    /// The first `usize` bytes aren't understood and should be thrown away
+    Skip(usize),
+    /// The input is the start of a sequence which hasn't been completely received yet
+    Incomplete,
+}
+
+/// Turns the bytes read from the terminal into [Event]s
+#[derive(Debug, Clone)]
+pub(crate) struct Decoder {
+    buffer: Vec<u8>,
+    /// the key sequences from the terminfo database
+    keys: Vec<(Vec<u8>, KeyEvent)>,
+    /// the markers sent before and after pasted text
+    paste_start: Vec<u8>,
+    paste_end: Vec<u8>,
+    /// the text pasted so far if the start of a paste has been received but not the end
+    paste: Option<Vec<u8>>,
+    pub paste_limit: usize,
+}
+
+impl Decoder {
+    pub fn new(database: &Database) -> Self {
+        let mut decoder = Self {
+            buffer: Vec::new(),
+            keys: Vec::new(),
+            paste_start: Vec::new(),
+            paste_end: Vec::new(),
+            paste: None,
+            paste_limit: DEFAULT_PASTE_LIMIT,
+        };
+
+        decoder.load_sequences(database);
+        decoder
+    }
+
+    /// Reads the key sequences and paste markers from `database`
+    pub fn load_sequences(&mut self, database: &Database) {
+        self.keys = KEY_CAPABILITIES.iter()
+            .filter_map(|(name, code, modifiers)| match database.raw(name) {
+                Some(terminfo::Value::String(sequence)) if !sequence.is_empty() => {
+                    Some((sequence.clone(), KeyEvent::new(*code, *modifiers)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let marker = |name, fallback: &[u8]| match database.raw(name) {
+            Some(terminfo::Value::String(sequence)) if !sequence.is_empty() => sequence.clone(),
+            _ => fallback.to_vec(),
+        };
+
+        self.paste_start = marker("PS", b"\x1b[200~");
+        self.paste_end = marker("PE", b"\x1b[201~");
+    }
+
+    /// Adds bytes read from the terminal to the end of the input
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Decodes the next event from the input received so far
+    ///
+    /// The end of the input is taken to be the end of what the terminal has sent, so an escape
+    /// byte at the end is the escape key rather than the start of a sequence. Sequences which have
+    /// clearly been cut off, such as `ESC [` or `ESC O` without a final byte, are kept until more
+    /// input is pushed, so alt + shift + O is only decoded once something follows it.
+    pub fn next(&mut self) -> Option<Event> {
+        loop {
+            if self.paste.is_some() {
+                return self.next_paste();
+            }
+
+            if self.buffer.is_empty() {
+                return None;
+            }
+
+            if self.buffer.starts_with(&self.paste_start) {
+                self.buffer.drain(..self.paste_start.len());
+                self.paste = Some(Vec::new());
+                continue;
+            }
+
+            match self.decode(&self.buffer) {
+                Decoded::Event(event, length) => {
+                    self.buffer.drain(..length);
+                    return Some(event);
+                }
+                Decoded::Skip(length) => {
+                    self.buffer.drain(..length);
+                }
+                Decoded::Incomplete => return None,
+            }
+        }
+    }
+
+    /// Moves pasted text out of the input, returning the paste once its end has been received
+    fn next_paste(&mut self) -> Option<Event> {
+        let end = self.buffer.windows(self.paste_end.len()).position(|window| window == self.paste_end);
+
+        // if the end hasn't been received, enough is kept to find it if it has been cut off
+        let length = end.unwrap_or_else(|| self.buffer.len().saturating_sub(self.paste_end.len() - 1));
+        let paste = self.paste.as_mut()?;
+
+        let kept = length.min(self.paste_limit.saturating_sub(paste.len()));
+        paste.extend_from_slice(&self.buffer[..kept]);
+        self.buffer.drain(..length);
+
+        end?;
+        self.buffer.drain(..self.paste_end.len());
+
+        let paste = self.paste.take()?;
+        let text = String::from_utf8_lossy(&paste).replace("\r\n", "\n").replace('\r', "\n");
+
+        Some(Event::Paste(text))
+    }
+
+    /// Removes and returns the first escape sequence in the input that `parse` accepts, leaving
+    /// everything else to be decoded into events
+    pub fn take_response<T>(&mut self, parse: &mut impl FnMut(&[u8]) -> Option<T>) -> Option<T> {
+        let mut start = 0;
+
+        while let Some(length) = unit_length(&self.buffer[start..]) {
+            let end = start + length;
+
+            if self.buffer[start] == ESC && let Some(response) = parse(&self.buffer[start..end]) {
+                self.buffer.drain(start..end);
+                return Some(response);
+            }
+
+            start = end;
+        }
+
+        None
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Decoded {
+
+        // the longest matching terminfo key sequence
+        let key = self.keys.iter()
+            .filter(|(sequence, _)| bytes.starts_with(sequence))
+            .max_by_key(|(sequence, _)| sequence.len());
+
+        if let Some((sequence, key)) = key {
+            return Decoded::Event(Event::Key(*key), sequence.len());
+        }
+
+        match bytes {
+            [ESC] => key_event(KeyCode::Esc, KeyModifiers::NONE, 1),
+            [ESC, b'[', ..] => decode_csi(bytes),
+            [ESC, b'O', third, ..] => decode_ss3(*third),
+            // an SS3 sequence whose final byte hasn't been received yet
+            [ESC, b'O'] => Decoded::Incomplete,
+            // replies to queries which weren't waited for
+            [ESC, b']' | b'P' | b'_' | b'^', _, ..] => match string_length(bytes) {
+                Some(length) => Decoded::Skip(length),
+                None => Decoded::Incomplete,
+            },
+            // the start of another escape sequence
+            [ESC, ESC, ..] => key_event(KeyCode::Esc, KeyModifiers::NONE, 1),
+            // alt is sent as an escape before the key
+            [ESC, rest @ ..] => match decode_plain(rest) {
+                Decoded::Event(Event::Key(key), length) => {
+                    key_event(key.code, key.modifiers | KeyModifiers::ALT, length + 1)
+                }
+                other => other,
+            },
+            _ => decode_plain(bytes),
+        }
+    }
+}
+
+/// Returns the length of the escape sequence or character at the start of `bytes`, or `None` if
+/// there is nothing there or it has been cut off
+fn unit_length(bytes: &[u8]) -> Option<usize> {
+    match bytes {
+        [] => None,
+        [ESC, b'[', ..] => csi_length(bytes),
+        [ESC, b']' | b'P' | b'_' | b'^', ..] => string_length(bytes),
+        [ESC, b'O', _, ..] => Some(3),
+        [ESC, b'O'] => None,
+        [ESC, _, ..] => Some(2),
+        [ESC] => Some(1),
+        [first, ..] => {
+            let length = match first {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+
+            (bytes.len() >= length).then_some(length)
+        }
+    }
+}
+
+/// Returns the length of the control sequence at the start of `bytes`, or `None` if it has been
+/// cut off. An invalid sequence ends at the first invalid byte.
+fn csi_length(bytes: &[u8]) -> Option<usize> {
+    let mut index = 2;
+
+    while bytes.get(index).is_some_and(|byte| (0x20..=0x3F).contains(byte)) {
+        index += 1;
+    }
+
+    match bytes.get(index)? {
+        0x40..=0x7E => Some(index + 1),
+        _ => Some(index),
+    }
+}
+
+/// Returns the length of the control string (OSC, DCS, APC or PM) at the start of `bytes`, which
+/// ends with ST (`ESC \`) or BEL, or `None` if its end hasn't been received
+fn string_length(bytes: &[u8]) -> Option<usize> {
+    (2..bytes.len()).find_map(|index| match bytes[index] {
+        0x07 => Some(index + 1),
+        ESC => (bytes.get(index + 1) == Some(&b'\\')).then_some(index + 2),
+        _ => None,
+    })
+}
+
+fn key_event(code: KeyCode, modifiers: KeyModifiers, length: usize) -> Decoded {
+    Decoded::Event(Event::Key(KeyEvent::new(code, modifiers)), length)
+}
+
+/// Decodes a single character or control character
+fn decode_plain(bytes: &[u8]) -> Decoded {
+    let Some(&first) = bytes.first() else {
+        return Decoded::Incomplete;
+    };
+
+    let control = |c: u8| key_event(KeyCode::Char(c as char), KeyModifiers::CONTROL, 1);
+
+    match first {
+        b'\r' => key_event(KeyCode::Enter, KeyModifiers::NONE, 1),
+        b'\t' => key_event(KeyCode::Tab, KeyModifiers::NONE, 1),
+        0x7F => key_event(KeyCode::Backspace, KeyModifiers::NONE, 1),
+        0x00 => control(b' '),
+        0x01..=0x1A => control(first - 1 + b'a'),
+        0x1C..=0x1F => control(first - 0x1C + b'4'),
+        _ => {
+            let length = match first {
+                0x00..=0x7F => 1,
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                // not the start of a UTF-8 character
+                _ => return Decoded::Skip(1),
+            };
+
+            if bytes.len() < length {
+                return Decoded::Incomplete;
+            }
+
+            match std::str::from_utf8(&bytes[..length]).ok().and_then(|s| s.chars().next()) {
+                Some(c) => key_event(KeyCode::Char(c), KeyModifiers::NONE, length),
+                None => Decoded::Skip(1),
+            }
+        }
+    }
+}
+
+/// Decodes `ESC O <byte>` sequences, which some terminals send for the arrow keys and F1-F4
+fn decode_ss3(byte: u8) -> Decoded {
+    let code = match byte {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'M' => KeyCode::Enter,
+        b'P'..=b'S' => KeyCode::F(byte - b'P' + 1),
+        _ => return Decoded::Skip(3),
+    };
+
+    key_event(code, KeyModifiers::NONE, 3)
+}
+
+/// Decodes a control sequence (`ESC [ <parameters> <intermediates> <final byte>`)
+fn decode_csi(bytes: &[u8]) -> Decoded {
+
+    let mut index = 2;
+
+    while bytes.get(index).is_some_and(|byte| (0x30..=0x3F).contains(byte)) {
+        index += 1;
+    }
+
+    let parameters = &bytes[2..index];
+
+    while bytes.get(index).is_some_and(|byte| (0x20..=0x2F).contains(byte)) {
+        index += 1;
+    }
+
+    let final_byte = match bytes.get(index) {
+        Some(byte @ 0x40..=0x7E) => *byte,
+        Some(_) => return Decoded::Skip(index),
+        None => return Decoded::Incomplete,
+    };
+
+    let length = index + 1;
+
+    match csi_event(parameters, final_byte) {
+        Some(event) => Decoded::Event(event, length),
+        None => Decoded::Skip(length),
+    }
+}
+
+/// Parses the parameters of a control sequence into fields separated by `;`, each of which is made
+/// of numbers separated by `:`. Missing numbers are `None`.
+fn csi_numbers(parameters: &[u8]) -> Vec<Vec<Option<u32>>> {
+    parameters.split(|byte| *byte == b';')
+        .map(|field| {
+            field.split(|byte| *byte == b':')
+                .map(|number| std::str::from_utf8(number).ok()?.parse().ok())
+                .collect()
+        })
+        .collect()
+}
+
+fn csi_event(parameters: &[u8], final_byte: u8) -> Option<Event> {
+
+    if let [b'?', flags @ ..] = parameters && final_byte == b'u' {
+        let flags = std::str::from_utf8(flags).ok()?.parse().ok()?;
+        return Some(Event::KeyboardEnhancementFlags(KeyboardEnhancementFlags::from_bits(flags)));
+    }
+
+    // other sequences with private parameters are replies to queries rather than keys
+    if parameters.first().is_some_and(|byte| (b'<'..=b'?').contains(byte)) {
+        return None;
+    }
+
+    let numbers = csi_numbers(parameters);
+    let number = |field: usize, index: usize| numbers.get(field)?.get(index).copied().flatten();
+
+    if final_byte == b'u' {
+        return kitty_key_event(&number);
+    }
+
+    let first = number(0, 0).unwrap_or(1);
+    let modifiers = KeyModifiers::from_xterm_parameter(number(1, 0).unwrap_or(1));
+    let kind = KeyEventKind::from_kitty_parameter(number(1, 1).unwrap_or(1));
+
+    let code = match final_byte {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        b'Z' => return Some(Event::Key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT))),
+        b'I' if parameters.is_empty() => return Some(Event::FocusGained),
+        b'O' if parameters.is_empty() => return Some(Event::FocusLost),
+        b'~' => match first {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            11..=15 => KeyCode::F((first - 10) as u8),
+            17..=21 => KeyCode::F((first - 11) as u8),
+            23..=26 => KeyCode::F((first - 12) as u8),
+            28 | 29 => KeyCode::F((first - 13) as u8),
+            31..=34 => KeyCode::F((first - 14) as u8),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(Event::Key(KeyEvent::new(code, modifiers).with_kind(kind)))
+}
+
+/// Decodes a kitty keyboard protocol key sequence (`CSI key[:shifted key] ; modifiers[:event type] u`)
+///
+/// Keys which have no [KeyCode], such as the modifier keys themselves and media keys, are ignored.
+fn kitty_key_event(number: &impl Fn(usize, usize) -> Option<u32>) -> Option<Event> {
+
+    let key = number(0, 0)?;
+    let modifiers = KeyModifiers::from_kitty_parameter(number(1, 0).unwrap_or(1));
+    let kind = KeyEventKind::from_kitty_parameter(number(1, 1).unwrap_or(1));
+    let shift = modifiers.contains(KeyModifiers::SHIFT);
+
+    let code = match key {
+        9 if shift => KeyCode::BackTab,
+        9 => KeyCode::Tab,
+        13 => KeyCode::Enter,
+        27 => KeyCode::Esc,
+        8 | 127 => KeyCode::Backspace,
+        57376..=57398 => KeyCode::F((key - 57376 + 13) as u8),
+        57399..=57408 => KeyCode::Char(char::from_digit(key - 57399, 10)?),
+        57409 => KeyCode::Char('.'),
+        57410 => KeyCode::Char('/'),
+        57411 => KeyCode::Char('*'),
+        57412 => KeyCode::Char('-'),
+        57413 => KeyCode::Char('+'),
+        57414 => KeyCode::Enter,
+        57415 => KeyCode::Char('='),
+        57416 => KeyCode::Char(','),
+        57417 => KeyCode::Left,
+        57418 => KeyCode::Right,
+        57419 => KeyCode::Up,
+        57420 => KeyCode::Down,
+        57421 => KeyCode::PageUp,
+        57422 => KeyCode::PageDown,
+        57423 => KeyCode::Home,
+        57424 => KeyCode::End,
+        57425 => KeyCode::Insert,
+        57426 => KeyCode::Delete,
+        // the rest of the private use area is used for keys without a KeyCode
+        0xE000..=0xF8FF => return None,
+        _ => {
+            // the shifted key is only sent with REPORT_ALTERNATE_KEYS
+            let c = match number(0, 1) {
+                Some(shifted) if shift => char::from_u32(shifted)?,
+                _ if shift => char::from_u32(key)?.to_ascii_uppercase(),
+                _ => char::from_u32(key)?,
+            };
+
+            KeyCode::Char(c)
+        }
+    };
+
+    Some(Event::Key(KeyEvent::new(code, modifiers).with_kind(kind)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder() -> Decoder {
+        let mut database = Database::new();
+        database.name("test");
+        Decoder::new(&database.build().unwrap())
+    }
+
+    /// Pushes `bytes` to a decoder with no terminfo key sequences and decodes every event
+    fn decode_all(bytes: &[u8]) -> Vec<Event> {
+        let mut decoder = decoder();
+        decoder.push(bytes);
+        std::iter::from_fn(|| decoder.next()).collect()
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn csi_keys() {
+        assert_eq!(decode_all(b"\x1b[A\x1b[D\x1b[H\x1b[F"), [
+            key(KeyCode::Up, KeyModifiers::NONE),
+            key(KeyCode::Left, KeyModifiers::NONE),
+            key(KeyCode::Home, KeyModifiers::NONE),
+            key(KeyCode::End, KeyModifiers::NONE),
+        ]);
+        assert_eq!(decode_all(b"\x1b[2~\x1b[3~\x1b[6~\x1b[15~\x1b[24~"), [
+            key(KeyCode::Insert, KeyModifiers::NONE),
+            key(KeyCode::Delete, KeyModifiers::NONE),
+            key(KeyCode::PageDown, KeyModifiers::NONE),
+            key(KeyCode::F(5), KeyModifiers::NONE),
+            key(KeyCode::F(12), KeyModifiers::NONE),
+        ]);
+        assert_eq!(decode_all(b"\x1b[Z"), [key(KeyCode::BackTab, KeyModifiers::SHIFT)]);
+    }
+
+    #[test]
+    fn ss3_keys() {
+        assert_eq!(decode_all(b"\x1bOA\x1bOP\x1bOS\x1bOM"), [
+            key(KeyCode::Up, KeyModifiers::NONE),
+            key(KeyCode::F(1), KeyModifiers::NONE),
+            key(KeyCode::F(4), KeyModifiers::NONE),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ]);
+        // unknown SS3 sequences are skipped rather than decoded as alt + O
+        assert_eq!(decode_all(b"\x1bOzq"), [key(KeyCode::Char('q'), KeyModifiers::NONE)]);
+    }
+
+    #[test]
+    fn terminfo_keys() {
+        let mut database = Database::new();
+        database.name("test").raw("kf1", b"\x1b[11~".as_slice()).raw("kcuu1", b"\x1bOA".as_slice());
+
+        let mut decoder = Decoder::new(&database.build().unwrap());
+        decoder.push(b"\x1b[11~\x1bOA");
+
+        assert_eq!(decoder.next(), Some(key(KeyCode::F(1), KeyModifiers::NONE)));
+        assert_eq!(decoder.next(), Some(key(KeyCode::Up, KeyModifiers::NONE)));
+        assert_eq!(decoder.next(), None);
+    }
+
+    #[test]
+    fn alt_prefix() {
+        assert_eq!(decode_all(b"\x1ba\x1b\x01\x1b\x7f"), [
+            key(KeyCode::Char('a'), KeyModifiers::ALT),
+            key(KeyCode::Char('a'), KeyModifiers::ALT | KeyModifiers::CONTROL),
+            key(KeyCode::Backspace, KeyModifiers::ALT),
+        ]);
+        assert_eq!(decode_all("\x1bé".as_bytes()), [key(KeyCode::Char('é'), KeyModifiers::ALT)]);
+    }
+
+    #[test]
+    fn lone_escape() {
+        assert_eq!(decode_all(b"\x1b"), [key(KeyCode::Esc, KeyModifiers::NONE)]);
+        assert_eq!(decode_all(b"\x1b\x1b[A"), [
+            key(KeyCode::Esc, KeyModifiers::NONE),
+            key(KeyCode::Up, KeyModifiers::NONE),
+        ]);
+    }
+
+    #[test]
+    fn split_reads() {
+        let mut decoder = decoder();
+
+        decoder.push(b"\x1b[1;5");
+        assert!(matches!(decoder.decode(&decoder.buffer), Decoded::Incomplete));
+        assert_eq!(decoder.next(), None);
+
+        decoder.push(b"A");
+        assert_eq!(decoder.next(), Some(key(KeyCode::Up, KeyModifiers::CONTROL)));
+
+        decoder.push(b"\xc3");
+        assert_eq!(decoder.next(), None);
+
+        decoder.push(b"\xa9");
+        assert_eq!(decoder.next(), Some(key(KeyCode::Char('é'), KeyModifiers::NONE)));
+
+        decoder.push(b"\x1b]11;rgb:0000/0000/0000");
+        assert_eq!(decoder.next(), None);
+
+        decoder.push(b"\x1b\\x");
+        assert_eq!(decoder.next(), Some(key(KeyCode::Char('x'), KeyModifiers::NONE)));
+        assert_eq!(decoder.next(), None);
+    }
+
+    #[test]
+    fn focus_events() {
+        assert_eq!(decode_all(b"\x1b[I\x1b[Ox\x1b[O"), [
+            Event::FocusGained,
+            Event::FocusLost,
+            key(KeyCode::Char('x'), KeyModifiers::NONE),
+            Event::FocusLost,
+        ]);
+
+        // with parameters these aren't focus events
+        assert_eq!(decode_all(b"\x1b[1I"), []);
+    }
+
+    #[test]
+    fn split_ss3_sequences() {
+        let mut decoder = decoder();
+
+        decoder.push(b"\x1bO");
+        assert_eq!(decoder.next(), None);
+        assert_eq!(unit_length(&decoder.buffer), None);
+
+        decoder.push(b"B");
+        assert_eq!(decoder.next(), Some(key(KeyCode::Down, KeyModifiers::NONE)));
+
+        // alt + shift + O is decoded once anything follows it
+        decoder.push(b"\x1bOx");
+        assert_eq!(decoder.next(), None);
+        assert!(decoder.buffer.is_empty());
+    }
+
+    #[test]
+    fn pastes() {
+        assert_eq!(decode_all(b"a\x1b[200~b\x1b[Ac\x1b[201~d"), [
+            key(KeyCode::Char('a'), KeyModifiers::NONE),
+            Event::Paste("b\x1b[Ac".to_owned()),
+            key(KeyCode::Char('d'), KeyModifiers::NONE),
+        ]);
+
+        // line endings are turned into \n
+        assert_eq!(decode_all(b"\x1b[200~a\r\nb\rc\nd\x1b[201~"), [Event::Paste("a\nb\nc\nd".to_owned())]);
+        assert_eq!(decode_all(b"\x1b[200~\x1b[201~"), [Event::Paste(String::new())]);
+    }
+
+    #[test]
+    fn pastes_split_across_reads() {
+        let mut decoder = decoder();
+
+        decoder.push(b"\x1b[200~hello ");
+        assert_eq!(decoder.next(), None);
+
+        // the end marker is cut off in the middle
+        decoder.push(b"world\x1b[20");
+        assert_eq!(decoder.next(), None);
+
+        decoder.push(b"1~x");
+        assert_eq!(decoder.next(), Some(Event::Paste("hello world".to_owned())));
+        assert_eq!(decoder.next(), Some(key(KeyCode::Char('x'), KeyModifiers::NONE)));
+
+        // a \r\n split across reads is still one line ending
+        decoder.push(b"\x1b[200~a\r");
+        assert_eq!(decoder.next(), None);
+
+        decoder.push(b"\nb\x1b[201~");
+        assert_eq!(decoder.next(), Some(Event::Paste("a\nb".to_owned())));
+        assert_eq!(decoder.next(), None);
+    }
+
+    #[test]
+    fn paste_limit() {
+        let mut decoder = decoder();
+        decoder.paste_limit = 4;
+
+        decoder.push(b"\x1b[200~abc");
+        assert_eq!(decoder.next(), None);
+
+        decoder.push(b"defgh\x1b[201~i");
+        assert_eq!(decoder.next(), Some(Event::Paste("abcd".to_owned())));
+
+        // what follows the paste is decoded as usual
+        assert_eq!(decoder.next(), Some(key(KeyCode::Char('i'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn modifier_parameters() {
+        assert_eq!(decode_all(b"\x1b[1;2C\x1b[1;3D\x1b[1;5A\x1b[1;8H\x1b[1;9B\x1b[5;5~"), [
+            key(KeyCode::Right, KeyModifiers::SHIFT),
+            key(KeyCode::Left, KeyModifiers::ALT),
+            key(KeyCode::Up, KeyModifiers::CONTROL),
+            key(KeyCode::Home, KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CONTROL),
+            // meta is reported as alt
+            key(KeyCode::Down, KeyModifiers::ALT),
+            key(KeyCode::PageUp, KeyModifiers::CONTROL),
+        ]);
+        assert_eq!(decode_all(b"\x1b[97;5u\x1b[97:65;2u\x1b[9;2u"), [
+            key(KeyCode::Char('a'), KeyModifiers::CONTROL),
+            key(KeyCode::Char('A'), KeyModifiers::SHIFT),
+            key(KeyCode::BackTab, KeyModifiers::SHIFT),
+        ]);
+        assert_eq!(decode_all(b"\x1b[1;1:3A"), [
+            Event::Key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE).with_kind(KeyEventKind::Release)),
+        ]);
+    }
+
+    #[test]
+    fn late_cursor_position_report() {
+        // a cursor position report which arrives after it was waited for looks the same as F3 with
+        // modifiers, which is what it is decoded as
+        assert_eq!(decode_all(b"\x1b[12;3R"), [key(KeyCode::F(3), KeyModifiers::ALT)]);
+
+        // while it is being waited for, it is taken out of the input before keys are decoded
+        let mut decoder = decoder();
+        decoder.push(b"x\x1b[12;3Ry");
+
+        let report = decoder.take_response(&mut |reply| crate::cursor::parse_position_report(reply, true));
+        assert_eq!(report, Some((2, 11)));
+        assert_eq!(decoder.next(), Some(key(KeyCode::Char('x'), KeyModifiers::NONE)));
+        assert_eq!(decoder.next(), Some(key(KeyCode::Char('y'), KeyModifiers::NONE)));
+        assert_eq!(decoder.next(), None);
+    }
+}
@@ -0,0 +1,415 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Asking the terminal what it supports, see [Terminal::probe](crate::Terminal::probe)
+//!
+//! The terminfo database is chosen by `TERM`, which is often wrong, especially over SSH where the
+//! local terminal's entry may not be installed. Probing asks the terminal itself with:
+//!
+//! - primary device attributes (DA1), which nearly every terminal answers. Other requests are sent
+//!   first, so once this is answered, anything unanswered is unsupported.
+//! - XTVERSION, which gives the terminal's name and version
+//! - XTGETTCAP, which gives the terminal's own value for a terminfo capability
+
+use {
+    std::{collections::BTreeMap, fmt},
+
+    terminfo::{names, Database, Value},
+};
+
+/// The capabilities asked for by [Terminal::probe](crate::Terminal::probe)
+pub const DEFAULT_CAPABILITIES: &[&str] = &[
+    "TN", "colors", "RGB", "Tc", "setrgbf", "setrgbb", "Smulx", "Setulc", "Ss", "Se", "Ms", "Sync",
+    "BE", "BD", "fe", "fd",
+];
+
+/// The user defined capabilities used by this crate, which are kept when probed capabilities are
+/// merged into a database
+pub(crate) const EXTENDED_CAPABILITIES: &[&str] = &[
+    "AX", "BD", "BE", "Cr", "Cs", "E3", "Ms", "PE", "PS", "RGB", "Rmol", "Se", "Setulc", "Smol",
+    "Smulx", "Ss", "Su", "Sxl", "Sync", "TS", "Tc", "XM", "XT", "fd", "fe", "kxIN", "kxOUT", "rmxx",
+    "setrgbb", "setrgbf", "smxx",
+];
+
+/// What a terminal reported about itself when probed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TerminalFeatures {
+    /// The terminal's name from XTVERSION, such as `xterm` or `kitty`
+    pub name: Option<String>,
+    /// The terminal's version from XTVERSION
+    pub version: Option<String>,
+    /// The parameters of the reply to primary device attributes. The first is the conformance
+    /// level, such as 62 for a VT220, and the rest are features, such as 4 for sixel graphics.
+    ///
+    /// `None` if the terminal didn't reply, in which case nothing else could be found out either.
+    pub device_attributes: Option<Vec<u16>>,
+    /// The value of each capability asked for with XTGETTCAP, or `None` for capabilities the
+    /// terminal said it doesn't have. Capabilities which weren't answered at all are left out.
+    pub capabilities: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl TerminalFeatures {
+    /// Returns true if the terminal replied to the probe at all
+    pub fn responded(&self) -> bool {
+        self.device_attributes.is_some()
+    }
+
+    /// Returns true if the terminal reported having the device attribute `attribute`
+    pub fn has_attribute(&self, attribute: u16) -> bool {
+        self.device_attributes.as_ref().is_some_and(|attributes| attributes.iter().skip(1).any(|a| *a == attribute))
+    }
+
+    /// Returns true if the terminal reported supporting sixel graphics
+    pub fn sixel(&self) -> bool {
+        self.has_attribute(4)
+    }
+
+    pub(crate) fn add(&mut self, reply: Reply) {
+        match reply {
+            Reply::DeviceAttributes(attributes) => self.device_attributes = Some(attributes),
+            Reply::Version(version) => {
+                let (name, version) = split_version(&version);
+                self.name = Some(name);
+                self.version = version;
+            }
+            Reply::Capability(name, value) => {
+                self.capabilities.insert(name, value);
+            }
+        }
+    }
+
+    /// Returns the capabilities the terminal reported, by their full names, with `None` for the
+    /// ones it said it doesn't have
+    ///
+    /// Sixel graphics are added as the `Sxl` capability if the device attributes list them. Numbers
+    /// which couldn't be parsed are left out.
+    pub fn overrides(&self) -> BTreeMap<String, Option<Value>> {
+        let full_name = |name: &str| names::ALIASES.get(name).copied().unwrap_or(name).to_owned();
+
+        let mut overrides: BTreeMap<String, Option<Value>> = self.capabilities.iter()
+            .filter_map(|(name, value)| {
+                let name = full_name(name);
+
+                match value {
+                    Some(value) => capability_value(&name, value).map(|value| (name, Some(value))),
+                    None => Some((name, None)),
+                }
+            })
+            .collect();
+
+        if self.sixel() {
+            overrides.insert(String::from("Sxl"), Some(Value::True));
+        }
+
+        overrides
+    }
+
+    /// Returns `database` with the capabilities the terminal reported added, replacing the ones
+    /// the database already had. Capabilities the terminal said it doesn't have are removed.
+    ///
+    /// terminfo has no way to list the capabilities in a database, so only the standard ones and
+    /// the user defined ones this crate uses are kept from `database`.
+    pub fn merge_into(&self, database: &Database) -> Database {
+        merge(database, &self.overrides())
+    }
+}
+
+impl fmt::Display for TerminalFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, &self.version) {
+            (Some(name), Some(version)) => writeln!(f, "terminal: {name} {version}")?,
+            (Some(name), None) => writeln!(f, "terminal: {name}")?,
+            _ => writeln!(f, "terminal: unknown")?,
+        }
+
+        match &self.device_attributes {
+            Some(attributes) => {
+                let attributes: Vec<String> = attributes.iter().map(u16::to_string).collect();
+                writeln!(f, "device attributes: {}", attributes.join(";"))?;
+            }
+            None => writeln!(f, "device attributes: no reply")?,
+        }
+
+        for (name, value) in &self.capabilities {
+            match value {
+                Some(value) => writeln!(f, "{name}: {}", String::from_utf8_lossy(value).escape_debug())?,
+                None => writeln!(f, "{name}: unsupported")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `database` with `overrides` taking the place of its own values
+///
+/// terminfo has no way to list the capabilities in a database, so besides `overrides` the new
+/// database only has the standard capabilities and the user defined ones in
+/// [EXTENDED_CAPABILITIES]. [Terminal::capability](crate::Terminal::capability) looks the others
+/// up in the original database.
+pub(crate) fn merge(database: &Database, overrides: &BTreeMap<String, Option<Value>>) -> Database {
+    let mut builder = Database::new();
+    builder.name(database.name())
+        .aliases(database.aliases().to_vec())
+        .description(database.description());
+
+    for (name, value) in overrides {
+        if let Some(value) = value {
+            builder.raw(name, value.clone());
+        }
+    }
+
+    let kept = names::BOOLEAN.values()
+        .chain(names::NUMBER.values())
+        .chain(names::STRING.values())
+        .chain(EXTENDED_CAPABILITIES);
+
+    for name in kept {
+        // capabilities the terminal doesn't have are left out
+        if overrides.contains_key(*name) {
+            continue;
+        }
+
+        if let Some(value) = database.raw(name) {
+            builder.raw(name, value.clone());
+        }
+    }
+
+    builder.build().unwrap_or_else(|_| database.clone())
+}
+
+/// Works out the type of a capability from its name, since XTGETTCAP only gives the value
+///
+/// Returns `None` for a number which can't be parsed.
+fn capability_value(name: &str, value: &[u8]) -> Option<Value> {
+    if names::BOOLEAN.values().any(|boolean| *boolean == name) {
+        return Some(Value::True);
+    }
+
+    if names::NUMBER.values().any(|number| *number == name) {
+        return std::str::from_utf8(value).ok()?.parse().ok().map(Value::Number);
+    }
+
+    // extended capabilities without a value are booleans
+    Some(match value.is_empty() {
+        true => Value::True,
+        false => Value::String(value.to_vec()),
+    })
+}
+
+/// Splits an XTVERSION reply, such as `xterm(372)` or `tmux 3.3a`, into a name and a version
+fn split_version(text: &str) -> (String, Option<String>) {
+    if let Some((name, version)) = text.strip_suffix(')').and_then(|text| text.split_once('(')) {
+        return (name.trim().to_owned(), Some(version.to_owned()));
+    }
+
+    match text.split_once(' ') {
+        Some((name, version)) => (name.to_owned(), Some(version.trim().to_owned())),
+        None => (text.to_owned(), None),
+    }
+}
+
+/// A reply to one of the requests sent when probing
+pub(crate) enum Reply {
+    DeviceAttributes(Vec<u16>),
+    Version(String),
+    Capability(String, Option<Vec<u8>>),
+}
+
+/// Writes the requests for the terminal's version, the capabilities `capabilities` and finally its
+/// device attributes
+pub(crate) fn probe_request(capabilities: &[&str]) -> Vec<u8> {
+    let mut request = b"\x1b[>0q".to_vec();
+
+    for name in capabilities {
+        request.extend_from_slice(b"\x1bP+q");
+        request.extend_from_slice(hex_encode(name.as_bytes()).as_bytes());
+        request.extend_from_slice(b"\x1b\\");
+    }
+
+    request.extend_from_slice(b"\x1b[c");
+    request
+}
+
+/// Parses a reply to one of the requests written by [probe_request]
+pub(crate) fn parse_reply(reply: &[u8]) -> Option<Reply> {
+
+    if let Some(attributes) = reply.strip_prefix(b"\x1b[?").and_then(|reply| reply.strip_suffix(b"c")) {
+        let attributes = std::str::from_utf8(attributes).ok()?
+            .split(';')
+            .filter_map(|attribute| attribute.parse().ok())
+            .collect();
+
+        return Some(Reply::DeviceAttributes(attributes));
+    }
+
+    let body = reply.strip_prefix(b"\x1bP")?;
+    let body = body.strip_suffix(b"\x1b\\").or_else(|| body.strip_suffix(b"\x07"))?;
+
+    if let Some(version) = body.strip_prefix(b">|") {
+        return Some(Reply::Version(String::from_utf8_lossy(version).into_owned()));
+    }
+
+    if let Some(capability) = body.strip_prefix(b"1+r") {
+        let (name, value) = match capability.iter().position(|byte| *byte == b'=') {
+            Some(index) => (&capability[..index], hex_decode(&capability[index + 1..])?),
+            None => (capability, Vec::new()),
+        };
+
+        return Some(Reply::Capability(String::from_utf8(hex_decode(name)?).ok()?, Some(value)));
+    }
+
+    if let Some(name) = body.strip_prefix(b"0+r") {
+        // some terminals don't repeat the name of a capability they don't have
+        let name = String::from_utf8(hex_decode(name)?).ok()?;
+        return (!name.is_empty()).then_some(Reply::Capability(name, None));
+    }
+
+    None
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+fn hex_decode(hex: &[u8]) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    // from_str_radix accepts a sign, which isn't hex
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    hex.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(hex_encode(b"Sxl"), "53786C");
+        assert_eq!(hex_decode(b"53786C"), Some(b"Sxl".to_vec()));
+        assert_eq!(hex_decode(b"53786c"), Some(b"Sxl".to_vec()));
+        assert_eq!(hex_decode(b""), Some(Vec::new()));
+
+        assert_eq!(hex_decode(b"537"), None);
+        assert_eq!(hex_decode(b"5G"), None);
+        assert_eq!(hex_decode(b"+5"), None);
+    }
+
+    #[test]
+    fn device_attributes() {
+        assert!(matches!(parse_reply(b"\x1b[?62;4;22c"), Some(Reply::DeviceAttributes(attributes)) if attributes == [62, 4, 22]));
+        assert!(matches!(parse_reply(b"\x1b[?1;2c"), Some(Reply::DeviceAttributes(attributes)) if attributes == [1, 2]));
+        assert!(parse_reply(b"\x1b[>1;2c").is_none());
+    }
+
+    #[test]
+    fn version() {
+        for reply in [b"\x1bP>|xterm(372)\x1b\\".as_slice(), b"\x1bP>|xterm(372)\x07"] {
+            assert!(matches!(parse_reply(reply), Some(Reply::Version(version)) if version == "xterm(372)"));
+        }
+
+        assert_eq!(split_version("xterm(372)"), ("xterm".to_owned(), Some("372".to_owned())));
+        assert_eq!(split_version("tmux 3.3a"), ("tmux".to_owned(), Some("3.3a".to_owned())));
+        assert_eq!(split_version("foot"), ("foot".to_owned(), None));
+    }
+
+    #[test]
+    fn capabilities() {
+        // `Tc` and `colors=256`, terminated with ST and BEL
+        for reply in [b"\x1bP1+r5463\x1b\\".as_slice(), b"\x1bP1+r5463\x07"] {
+            assert!(matches!(parse_reply(reply), Some(Reply::Capability(name, Some(value))) if name == "Tc" && value.is_empty()));
+        }
+
+        assert!(matches!(
+            parse_reply(b"\x1bP1+r636F6C6F7273=323536\x1b\\"),
+            Some(Reply::Capability(name, Some(value))) if name == "colors" && value == b"256"
+        ));
+        assert!(matches!(parse_reply(b"\x1bP0+r5378\x1b\\"), Some(Reply::Capability(name, None)) if name == "Sx"));
+
+        // an unsupported capability without its name can't be matched to a request
+        assert!(parse_reply(b"\x1bP0+r\x1b\\").is_none());
+    }
+
+    #[test]
+    fn malformed_capabilities() {
+        // bad hex in the name or the value
+        assert!(parse_reply(b"\x1bP1+r54ZZ\x1b\\").is_none());
+        assert!(parse_reply(b"\x1bP1+r5463=3\x1b\\").is_none());
+        assert!(parse_reply(b"\x1bP1+r5463=-1\x1b\\").is_none());
+        // not terminated
+        assert!(parse_reply(b"\x1bP1+r5463").is_none());
+        assert!(parse_reply(b"\x1bP1+r5463\x1b").is_none());
+        // some other DCS reply
+        assert!(parse_reply(b"\x1bP1$r0m\x1b\\").is_none());
+    }
+
+    #[test]
+    fn merge() {
+        let mut database = Database::new();
+        database.name("test")
+            .raw("colors", 8)
+            .raw("bce", Value::True)
+            .raw("cup", b"\x1b[%i%p1%d;%p2%dH".as_slice())
+            .raw("Smulx", b"\x1b[4:%p1%dm".as_slice())
+            .raw("Xcustom", b"custom".as_slice())
+            .raw("Ms", b"\x1b]52;%p1%s;%p2%s\x07".as_slice());
+        let database = database.build().unwrap();
+
+        let mut features = TerminalFeatures::default();
+        features.add(Reply::DeviceAttributes(vec![62, 4]));
+        features.add(Reply::Capability("colors".to_owned(), Some(b"256".to_vec())));
+        features.add(Reply::Capability("Tc".to_owned(), Some(Vec::new())));
+        features.add(Reply::Capability("Ms".to_owned(), None));
+        // a number that can't be parsed doesn't replace the database's
+        features.add(Reply::Capability("it".to_owned(), Some(b"eight".to_vec())));
+
+        assert_eq!(features.overrides().get("init_tabs"), None);
+
+        let merged = features.merge_into(&database);
+
+        assert_eq!(merged.name(), "test");
+        // probed capabilities are added or replace the database's, or removed if unsupported
+        assert_eq!(merged.raw("colors"), Some(&Value::Number(256)));
+        assert_eq!(merged.raw("Tc"), Some(&Value::True));
+        assert_eq!(merged.raw("Sxl"), Some(&Value::True));
+        assert_eq!(merged.raw("Ms"), None);
+        // everything else is kept, except user defined capabilities this crate doesn't use
+        for name in ["bce", "cup", "Smulx"] {
+            assert_eq!(merged.raw(name), database.raw(name), "{name}");
+        }
+        assert_eq!(merged.raw("Xcustom"), None);
+    }
+
+    #[test]
+    fn capability_types() {
+        // names are the full names, which merge_into gives
+        assert_eq!(capability_value("max_colors", b"256"), Some(Value::Number(256)));
+        assert_eq!(capability_value("max_colors", b"many"), None);
+        assert_eq!(capability_value("back_color_erase", b""), Some(Value::True));
+        assert_eq!(capability_value("Smulx", b"\x1b[4:%p1%dm"), Some(Value::String(b"\x1b[4:%p1%dm".to_vec())));
+        assert_eq!(capability_value("Tc", b""), Some(Value::True));
+    }
+}
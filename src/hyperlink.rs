@@ -0,0 +1,282 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Clickable links in text, using the operating system command for hyperlinks (OSC 8)
+//!
+//! Most modern terminals show text written between the start and end of a link like a link on a
+//! web page. A whole piece of text can be written as a link with [Hyperlink], and part of a styled
+//! line can be made a link with [Span::link](crate::text::Span::link).
+//!
+//! Terminals known not to support hyperlinks get the text followed by the URI in brackets instead,
+//! so the URI isn't lost.
+
+use {
+    crate::{command::Context, Command, Capability},
+
+    std::{io, sync::atomic::{AtomicUsize, Ordering}},
+
+    terminfo::Database,
+};
+
+/// The target of a link, which is part of a [Span](crate::text::Span)
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Link {
+    pub uri: String,
+    /// Terminals treat pieces of text with the same id and URI as one link, for example when
+    /// highlighting it as the mouse is over it. Links without an id are only joined up if they are
+    /// written without a break.
+    pub id: Option<String>,
+}
+
+impl Link {
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            id: None,
+        }
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Gives this link a unique id if it doesn't have one, so that it is still treated as one link
+    /// when it is broken across lines
+    pub(crate) fn ensure_id(&mut self) {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        if self.id.is_none() {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            // the process id keeps the ids of different programs writing to the same terminal apart
+            self.id = Some(format!("supaterm-{}-{id}", std::process::id()));
+        }
+    }
+
+    /// Writes the start of this link. Everything written until [write_end](Self::write_end) is
+    /// part of it.
+    pub(crate) fn write_start(&self, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b]8;")?;
+
+        if let Some(id) = &self.id {
+            // the parameters are separated by colons and end at a semicolon
+            target.write_all(b"id=")?;
+            target.write_all(escape(id, b":;").as_bytes())?;
+        }
+
+        target.write_all(b";")?;
+        target.write_all(escape(&self.uri, b"").as_bytes())?;
+        target.write_all(b"\x1b\\")
+    }
+
+    /// Writes the end of this link, whose text was `text`. If the terminal doesn't support
+    /// hyperlinks (`supported` is false), the URI is written instead, unless it is the same as the
+    /// text.
+    pub(crate) fn write_end(&self, text: &str, supported: bool, target: &mut dyn io::Write) -> io::Result<()> {
+        if supported {
+            return target.write_all(b"\x1b]8;;\x1b\\");
+        }
+
+        match text == self.uri {
+            true => Ok(()),
+            false => write!(target, " ({})", self.uri),
+        }
+    }
+}
+
+/// Percent encodes the bytes of `text` which can't be written in an operating system command,
+/// which are those outside the printable ASCII characters, and `reserved`. URIs are usually
+/// percent encoded already, so `%` is left alone.
+fn escape(text: &str, reserved: &[u8]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        match (0x20..=0x7E).contains(&byte) && !reserved.contains(&byte) {
+            true => escaped.push(byte as char),
+            false => escaped.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    escaped
+}
+
+/// Returns false for terminals which are known to show the sequences for hyperlinks as text, or to
+/// drop them, so that the URI is written after the text instead
+pub(crate) fn supports_hyperlinks(database: &Database) -> bool {
+    const UNSUPPORTED: &[&str] = &["linux", "vt", "dumb", "cons", "ansi", "sun", "eterm", "screen"];
+
+    // a family is followed by its variant, such as `vt100` or `linux-16color`, which keeps `vt`
+    // from matching `vte`
+    let in_family = |name: &str, family: &str| {
+        name.strip_prefix(family).is_some_and(|variant| !variant.starts_with(|c: char| c.is_ascii_alphabetic()))
+    };
+
+    std::iter::once(database.name())
+        .chain(database.aliases().iter().map(String::as_str))
+        .all(|name| !UNSUPPORTED.iter().any(|family| in_family(name, family)))
+}
+
+/// Writes `text` as a link to `uri`
+///
+/// On terminals known not to support hyperlinks, `text (uri)` is written instead.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Hyperlink {
+    pub uri: String,
+    /// See [Link::id]
+    pub id: Option<String>,
+    pub text: String,
+}
+
+impl Hyperlink {
+    pub fn new(uri: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            id: None,
+            text: text.into(),
+        }
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+}
+
+impl Command for Hyperlink {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.uri.len() * 2 + self.text.len() + 16)
+    }
+
+    fn write_to(&self, database: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let link = Link {
+            uri: self.uri.clone(),
+            id: self.id.clone(),
+        };
+
+        let supported = supports_hyperlinks(database);
+
+        if supported {
+            link.write_start(target)?;
+        }
+
+        target.write_all(self.text.as_bytes())?;
+        link.write_end(&self.text, supported, target)
+    }
+}
+
+impl Capability for Hyperlink {
+    fn is_supported(&self, _: &Database) -> bool {
+        // the text is written either way
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::text::{wrap, Span, WrapOptions},
+    };
+
+    fn database(name: &str, aliases: &[&str]) -> Database {
+        let mut database = Database::new();
+        database.name(name).aliases(aliases.iter().map(|alias| alias.to_string()).collect::<Vec<_>>());
+        database.build().unwrap()
+    }
+
+    fn written(hyperlink: Hyperlink, database: &Database) -> String {
+        let mut output = Vec::new();
+        hyperlink.write_to(database, &mut Context::new(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("a:b;c d", b":;"), "a%3Ab%3Bc d");
+        assert_eq!(escape("https://example.com/a;b?c=d", b""), "https://example.com/a;b?c=d");
+
+        // control characters and non-ASCII bytes would end or break the sequence
+        assert_eq!(escape("file:///tmp/\x1b]x\x07\u{e9}", b""), "file:///tmp/%1B]x%07%C3%A9");
+        assert_eq!(escape("50%25", b""), "50%25");
+    }
+
+    #[test]
+    fn links() {
+        let database = database("xterm-256color", &[]);
+
+        assert_eq!(
+            written(Hyperlink::new("https://example.com", "example").id("a:b"), &database),
+            "\x1b]8;id=a%3Ab;https://example.com\x1b\\example\x1b]8;;\x1b\\",
+        );
+    }
+
+    #[test]
+    fn families_without_hyperlinks() {
+        for name in ["linux", "linux-16color", "vt100", "vt220", "dumb", "screen.xterm-256color", "screen-256color", "ansi"] {
+            assert!(!supports_hyperlinks(&database(name, &[])), "{name}");
+        }
+
+        // vte isn't in the vt family
+        for name in ["vte", "vte-256color", "xterm-256color", "screenless", "alacritty"] {
+            assert!(supports_hyperlinks(&database(name, &[])), "{name}");
+        }
+
+        assert!(!supports_hyperlinks(&database("custom", &["vt102"])));
+    }
+
+    #[test]
+    fn fallback_text() {
+        let database = database("vt100", &[]);
+
+        assert_eq!(written(Hyperlink::new("https://example.com", "example"), &database), "example (https://example.com)");
+
+        // text which is already the URI isn't followed by it again
+        assert_eq!(written(Hyperlink::new("https://example.com", "https://example.com"), &database), "https://example.com");
+    }
+
+    #[test]
+    fn wrapped_links_keep_one_id() {
+        let spans = [
+            Span::plain("see "),
+            Span::plain("the example site").link(Link::new("https://example.com")),
+        ];
+
+        let lines = wrap(&spans, &WrapOptions::new(10));
+        let links: Vec<&Link> = lines.iter()
+            .flat_map(|line| &line.spans)
+            .filter_map(|span| span.link.as_ref())
+            .collect();
+
+        assert!(lines.len() > 1);
+        assert!(links.len() > 1);
+        assert!(links[0].id.is_some());
+        assert!(links.iter().all(|link| *link == links[0]));
+
+        // separate links are given different ids
+        let spans = [
+            Span::plain("one").link(Link::new("https://example.com")),
+            Span::plain(" two").link(Link::new("https://example.org")),
+        ];
+
+        let lines = wrap(&spans, &WrapOptions::new(80));
+        let ids: Vec<_> = lines[0].spans.iter().filter_map(|span| span.link.as_ref()?.id.clone()).collect();
+
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+}
@@ -0,0 +1,196 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Showing images in the terminal
+//!
+//! An [Image] is a buffer of RGBA pixels, which can be shown with any of the graphics protocols
+//! terminals support:
+//!
+//! - [kitty], which shows images in full color and keeps them in the terminal to be shown again
+//! - [sixel], the oldest and most widely supported, which is limited to a palette of colors
+
+pub mod kitty;
+pub mod sixel;
+
+use {
+    crate::sys,
+
+    std::io,
+};
+
+/// An image made of RGBA pixels, 4 bytes per pixel, in rows from the top left
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image from RGBA pixels
+    ///
+    /// Returns an error with an `ErrorKind` of `InvalidInput` if `pixels` isn't `width * height * 4`
+    /// bytes long.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> io::Result<Self> {
+        if pixels.len() as u64 != width as u64 * height as u64 * 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} bytes of pixels were given for a {width}x{height} image, which needs {}", pixels.len(), width as u64 * height as u64 * 4),
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Creates an image from RGB pixels, 3 bytes per pixel, which are all opaque
+    pub fn from_rgb(width: u32, height: u32, pixels: &[u8]) -> io::Result<Self> {
+        let pixels = pixels.chunks_exact(3).flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255]).collect();
+        Self::from_rgba(width, height, pixels)
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The RGBA pixels of the image
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the RGBA components of the pixel at column `x` and row `y`
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[index], self.pixels[index + 1], self.pixels[index + 2], self.pixels[index + 3]]
+    }
+
+    /// Returns this image scaled to `width` by `height` pixels
+    ///
+    /// Each pixel of the new image is the average of the pixels it covers, or the closest pixel
+    /// when the image is enlarged.
+    pub fn resize(&self, width: u32, height: u32) -> Self {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+
+        if self.width == 0 || self.height == 0 {
+            return Self {
+                width,
+                height,
+                pixels: vec![0; width as usize * height as usize * 4],
+            };
+        }
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+
+        // the range of pixels in this image which are covered by pixel `index` of the new image,
+        // which has `new_size` pixels along the axis
+        let covered = |index: u32, new_size: u32, size: u32| {
+            let start = index as u64 * size as u64 / new_size as u64;
+            let end = ((index as u64 + 1) * size as u64).div_ceil(new_size as u64);
+            start as u32..end.max(start + 1) as u32
+        };
+
+        for y in 0..height {
+            let rows = covered(y, height, self.height);
+
+            for x in 0..width {
+                let columns = covered(x, width, self.width);
+
+                let mut sum = [0u64; 4];
+                let mut count = 0;
+
+                for source_y in rows.clone() {
+                    for source_x in columns.clone() {
+                        let pixel = self.pixel(source_x, source_y);
+                        // colors are weighted by alpha so transparent pixels don't darken edges
+                        let alpha = pixel[3] as u64;
+
+                        for channel in 0..3 {
+                            sum[channel] += pixel[channel] as u64 * alpha;
+                        }
+
+                        sum[3] += alpha;
+                        count += 1;
+                    }
+                }
+
+                match sum[3] {
+                    0 => pixels.extend([0, 0, 0, 0]),
+                    alpha => pixels.extend([
+                        (sum[0] / alpha) as u8,
+                        (sum[1] / alpha) as u8,
+                        (sum[2] / alpha) as u8,
+                        (alpha / count) as u8,
+                    ]),
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// The size of a cell in pixels, from the window size the OS reports. Terminals which don't report
+/// it are assumed to use 10x20 pixel cells.
+///
+/// The size is always asked for on standard output, or standard input if that isn't a terminal,
+/// since a [Terminal](crate::Terminal)'s writer can be anything. Images written to another
+/// terminal are scaled for the cells of the one the process runs in.
+pub(crate) fn cell_size() -> (u32, u32) {
+    match sys::window_size() {
+        Ok(size) if size.columns > 0 && size.rows > 0 && size.width > 0 && size.height > 0 => {
+            ((size.width / size.columns).max(1) as u32, (size.height / size.rows).max(1) as u32)
+        }
+        _ => (10, 20),
+    }
+}
+
+/// Returns the size in pixels to show `image` at so that it takes up `columns` by `rows` cells
+///
+/// If only one of them is given, the other is chosen to keep the image's aspect ratio. If neither
+/// is given, the image's own size is used.
+pub(crate) fn fit(image: &Image, columns: Option<u16>, rows: Option<u16>) -> (u32, u32) {
+    let (cell_width, cell_height) = cell_size();
+
+    let (width, height) = (image.width.max(1) as u64, image.height.max(1) as u64);
+
+    match (columns, rows) {
+        (Some(columns), Some(rows)) => (columns as u32 * cell_width, rows as u32 * cell_height),
+        (Some(columns), None) => {
+            let new_width = columns as u64 * cell_width as u64;
+            (new_width as u32, (new_width * height / width).max(1) as u32)
+        }
+        (None, Some(rows)) => {
+            let new_height = rows as u64 * cell_height as u64;
+            ((new_height * width / height).max(1) as u32, new_height as u32)
+        }
+        (None, None) => (image.width, image.height),
+    }
+}
@@ -0,0 +1,631 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! The kitty graphics protocol, which kitty, WezTerm, Ghostty and Konsole support
+//!
+//! Images are sent to the terminal once with [Transmit] and given an id, and can then be shown any
+//! number of times with [Place] and removed with [DeleteImage]. Images are shown in full color
+//! and can be layered above or below text.
+//!
+//! Inside tmux, images are shown with [Placeholder] instead, which writes text that the terminal
+//! replaces with the image, so the image moves with the text as tmux scrolls and redraws the
+//! window. The sequences are passed through tmux to the terminal, which needs
+//! `set -g allow-passthrough on` in tmux's configuration.
+//!
+//! Whether the terminal supports the protocol can be found out with
+//! [Terminal::supports_kitty_graphics](crate::Terminal::supports_kitty_graphics).
+
+use {
+    super::Image,
+
+    crate::{
+        ansi_capability,
+        base64,
+        command::{is_ansi_terminal, Context},
+        cursor,
+        style::{direct_color, ContentStyle},
+        Command,
+        Capability,
+    },
+
+    std::io,
+
+    terminfo::Database,
+};
+
+/// The most base64 encoded bytes which can be sent in one graphics command
+const CHUNK_SIZE: usize = 4096;
+
+/// The image data sent by [Transmit]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ImageData<'a> {
+    /// The pixels of an image, sent as they are
+    Rgba(&'a Image),
+    /// A PNG file, which the terminal decodes itself
+    Png(&'a [u8]),
+}
+
+/// Sends an image to the terminal, which keeps it with the id `self.id` until it is deleted with
+/// [DeleteImage]
+///
+/// Ids start at 1, and sending another image with the same id replaces the old one. Nothing is
+/// shown until the image is placed with [Place] or [Placeholder].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Transmit<'a> {
+    pub id: u32,
+    pub data: ImageData<'a>,
+}
+
+impl<'a> Transmit<'a> {
+    pub const fn rgba(id: u32, image: &'a Image) -> Self {
+        Self {
+            id,
+            data: ImageData::Rgba(image),
+        }
+    }
+
+    pub const fn png(id: u32, png: &'a [u8]) -> Self {
+        Self {
+            id,
+            data: ImageData::Png(png),
+        }
+    }
+}
+
+impl Command for Transmit<'_> {
+    fn size_hint(&self) -> Option<usize> {
+        let length = match self.data {
+            ImageData::Rgba(image) => image.pixels().len(),
+            ImageData::Png(png) => png.len(),
+        };
+
+        let encoded = length.div_ceil(3) * 4;
+        Some(encoded + encoded.div_ceil(CHUNK_SIZE) * 24 + 32)
+    }
+
+    fn write_to(&self, _: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let (control, data) = match self.data {
+            ImageData::Rgba(image) => (format!("a=t,f=32,s={},v={}", image.width(), image.height()), image.pixels()),
+            ImageData::Png(png) => ("a=t,f=100".to_owned(), png),
+        };
+
+        let encoded = base64::encode(data);
+        let mut chunks = encoded.as_bytes().chunks(CHUNK_SIZE).peekable();
+
+        // the first chunk has the keys describing the image, and `m=1` on every chunk but the
+        // last says more data follows
+        let first = chunks.next().unwrap_or_default();
+        let more = chunks.peek().is_some() as u8;
+
+        write_graphics(&format!("{control},i={},q=2,m={more}", self.id), first, ctx, target)?;
+
+        while let Some(chunk) = chunks.next() {
+            let more = chunks.peek().is_some() as u8;
+            write_graphics(&format!("q=2,m={more}"), chunk, ctx, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+ansi_capability!(Transmit<'_>);
+
+/// Shows the image with the id `id`, sent with [Transmit], at the cursor or at the given cell
+///
+/// The image is scaled to fill the given number of columns and rows, and is shown at its own size
+/// otherwise. The cursor is left at the top left of the image.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Place {
+    id: u32,
+    placement: Option<u32>,
+    position: Option<(u16, u16)>,
+    columns: Option<u16>,
+    rows: Option<u16>,
+    z_index: i32,
+}
+
+impl Place {
+    pub const fn new(id: u32) -> Self {
+        Self {
+            id,
+            placement: None,
+            position: None,
+            columns: None,
+            rows: None,
+            z_index: 0,
+        }
+    }
+
+    /// Gives this placement an id, so that it can be moved by placing it again or deleted with
+    /// [DeletePlacement]. Without one, every placement of the image is a new one.
+    pub const fn placement(mut self, placement: u32) -> Self {
+        self.placement = Some(placement);
+        self
+    }
+
+    /// Shows the image with its top left at column `column` and row `row`, where (0, 0) is the top
+    /// left cell, instead of at the cursor
+    pub const fn at(mut self, column: u16, row: u16) -> Self {
+        self.position = Some((column, row));
+        self
+    }
+
+    pub const fn columns(mut self, columns: u16) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub const fn rows(mut self, rows: u16) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    /// Sets which images and text this image is drawn above. Images with a negative z-index are
+    /// drawn below text, and those below `-1073741824` are drawn below text with a background
+    /// color too.
+    pub const fn z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+impl Command for Place {
+    fn size_hint(&self) -> Option<usize> {
+        Some(64)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        if let Some((column, row)) = self.position {
+            cursor::MoveTo(column, row).write_to(database, ctx, target)?;
+        }
+
+        let mut control = format!("a=p,i={}", self.id);
+
+        if let Some(placement) = self.placement {
+            control.push_str(&format!(",p={placement}"));
+        }
+
+        if let Some(columns) = self.columns {
+            control.push_str(&format!(",c={columns}"));
+        }
+
+        if let Some(rows) = self.rows {
+            control.push_str(&format!(",r={rows}"));
+        }
+
+        // `C=1` keeps the cursor where it is, rather than moving it after the image
+        control.push_str(&format!(",z={},C=1,q=2", self.z_index));
+
+        write_graphics(&control, b"", ctx, target)
+    }
+}
+
+impl Capability for Place {
+    fn is_supported(&self, database: &Database) -> bool {
+        is_ansi_terminal(database) && (self.position.is_none() || cursor::MoveTo(0, 0).is_supported(database))
+    }
+}
+
+/// Deletes the image with the id `self.0` and every placement of it, freeing the terminal's copy
+/// of it
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct DeleteImage(pub u32);
+
+impl Command for DeleteImage {
+    fn size_hint(&self) -> Option<usize> {
+        Some(32)
+    }
+
+    fn write_to(&self, _: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write_graphics(&format!("a=d,d=I,i={},q=2", self.0), b"", ctx, target)
+    }
+}
+
+ansi_capability!(DeleteImage);
+
+/// Removes the placement with the id `self.1` of the image with the id `self.0`, keeping the image
+/// so it can be placed again
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct DeletePlacement(pub u32, pub u32);
+
+impl Command for DeletePlacement {
+    fn size_hint(&self) -> Option<usize> {
+        Some(40)
+    }
+
+    fn write_to(&self, _: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write_graphics(&format!("a=d,d=i,i={},p={},q=2", self.0, self.1), b"", ctx, target)
+    }
+}
+
+ansi_capability!(DeletePlacement);
+
+/// Deletes every image, freeing the terminal's copies of them
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct DeleteAllImages;
+
+impl Command for DeleteAllImages {
+    fn size_hint(&self) -> Option<usize> {
+        Some(16)
+    }
+
+    fn write_to(&self, _: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write_graphics("a=d,d=A,q=2", b"", ctx, target)
+    }
+}
+
+ansi_capability!(DeleteAllImages);
+
+/// Shows the image with the id `id`, sent with [Transmit], by writing Unicode placeholders at the
+/// cursor, which the terminal replaces with the image
+///
+/// The placeholders are ordinary text, so the image scrolls with the text around it, and programs
+/// such as tmux which redraw the screen themselves redraw the image too. The image is scaled to
+/// fill `columns` by `rows` cells (at most 297 of each) and the cursor is left after its bottom
+/// right cell. The placeholders are written with their own foreground and underline colors, so
+/// [style](Self::style) is set after them rather than the colors the cursor had before.
+///
+/// The image id is written as the foreground color, so ids above 255 need 24-bit color, which
+/// tmux needs the `RGB` or `Tc` feature for. They are unsupported on terminals without it.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Placeholder {
+    pub id: u32,
+    /// The id of the placement, which is needed to show the same image at different sizes
+    pub placement: Option<u32>,
+    pub columns: u16,
+    pub rows: u16,
+    /// The style set after the placeholders, which is no style by default
+    pub style: ContentStyle,
+}
+
+impl Placeholder {
+    pub const fn new(id: u32, columns: u16, rows: u16) -> Self {
+        Self {
+            id,
+            placement: None,
+            columns,
+            rows,
+            style: ContentStyle::new(),
+        }
+    }
+
+    pub const fn placement(mut self, placement: u32) -> Self {
+        self.placement = Some(placement);
+        self
+    }
+
+    /// Sets the style written after the placeholders, such as the style of the text the image is
+    /// shown in
+    pub const fn style(mut self, style: ContentStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Returns true if the ids have to be written as 24-bit colors
+    fn needs_direct_color(&self) -> bool {
+        self.id > 255 || self.placement.is_some_and(|placement| placement > 255)
+    }
+}
+
+impl Command for Placeholder {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.columns as usize * self.rows as usize * 10 + self.rows as usize * 8 + 96)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        if self.needs_direct_color() && !direct_color(database) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "image and placement ids above 255 need a terminal which supports 24-bit color"));
+        }
+
+        let columns = self.columns.min(DIACRITICS.len() as u16);
+        let rows = self.rows.min(DIACRITICS.len() as u16);
+
+        // a virtual placement (`U=1`) is shown wherever its placeholders are written
+        let mut control = format!("a=p,U=1,i={},c={columns},r={rows}", self.id);
+
+        if let Some(placement) = self.placement {
+            control.push_str(&format!(",p={placement}"));
+        }
+
+        control.push_str(",q=2");
+        write_graphics(&control, b"", ctx, target)?;
+
+        // the image id is given by the foreground color and the placement id by the underline
+        // color, with the most significant byte of the image id as a third diacritic
+        target.write_all(id_color(38, self.id).as_bytes())?;
+
+        if let Some(placement) = self.placement {
+            target.write_all(id_color(58, placement).as_bytes())?;
+        }
+
+        let most_significant = match self.id >> 24 {
+            0 => None,
+            byte => Some(DIACRITICS[byte as usize]),
+        };
+
+        for row in 0..rows {
+            let mut line = String::with_capacity(columns as usize * 10);
+
+            for column in 0..columns {
+                line.push(PLACEHOLDER);
+                line.push(DIACRITICS[row as usize]);
+                line.push(DIACRITICS[column as usize]);
+                line.extend(most_significant);
+            }
+
+            target.write_all(line.as_bytes())?;
+
+            if row + 1 < rows {
+                // index (IND) moves down a line, scrolling if needed, without changing column
+                target.write_all(b"\x1bD")?;
+                cursor::MoveLeft(columns).write_to(database, ctx, target)?;
+            }
+        }
+
+        self.style.write_to(database, ctx, target)
+    }
+}
+
+impl Capability for Placeholder {
+    fn is_supported(&self, database: &Database) -> bool {
+        is_ansi_terminal(database)
+            && cursor::MoveLeft(1).is_supported(database)
+            && (!self.needs_direct_color() || direct_color(database))
+    }
+}
+
+/// Returns the SGR sequence setting a color (38 for foreground or 58 for underline) to `id`, using
+/// an indexed color if it fits and otherwise the low 24 bits as a direct color
+fn id_color(sgr: u8, id: u32) -> String {
+    match id {
+        0..=255 => format!("\x1b[{sgr};5;{id}m"),
+        _ => format!("\x1b[{sgr};2;{};{};{}m", (id >> 16) as u8, (id >> 8) as u8, id as u8),
+    }
+}
+
+/// Writes a graphics command with the control data (the keys) `control` and `payload`, passing it
+/// through to the outer terminal when inside tmux (see [Context::inside_tmux])
+fn write_graphics(control: &str, payload: &[u8], ctx: &Context, target: &mut dyn io::Write) -> io::Result<()> {
+    let mut command = format!("\x1b_G{control}").into_bytes();
+
+    if !payload.is_empty() {
+        command.push(b';');
+        command.extend_from_slice(payload);
+    }
+
+    command.extend_from_slice(b"\x1b\\");
+
+    if !ctx.inside_tmux() {
+        return target.write_all(&command);
+    }
+
+    // tmux passes the contents of its own device control string through, with escapes doubled
+    let mut wrapped = b"\x1bPtmux;".to_vec();
+
+    for byte in command {
+        if byte == b'\x1b' {
+            wrapped.push(b'\x1b');
+        }
+
+        wrapped.push(byte);
+    }
+
+    wrapped.extend_from_slice(b"\x1b\\");
+    target.write_all(&wrapped)
+}
+
+/// A query for a 1x1 image which isn't kept, which terminals supporting the protocol reply to
+pub(crate) const SUPPORT_QUERY: &[u8] = b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// Parses a reply to [SUPPORT_QUERY], such as `ESC _ G i=31;OK ESC \`, into whether the image
+/// could be loaded
+pub(crate) fn parse_support_reply(reply: &[u8]) -> Option<bool> {
+    let body = reply.strip_prefix(b"\x1b_Gi=31;")?;
+    let body = body.strip_suffix(b"\x1b\\").or_else(|| body.strip_suffix(b"\x07"))?;
+
+    Some(body == b"OK")
+}
+
+/// The character the terminal replaces with part of an image
+const PLACEHOLDER: char = '\u{10EEEE}';
+
+/// The combining characters written after each [PLACEHOLDER] to give its row and column, in order
+/// from 0
+const DIACRITICS: [char; 297] = [
+    '\u{0305}', '\u{030D}', '\u{030E}', '\u{0310}', '\u{0312}', '\u{033D}', '\u{033E}', '\u{033F}', '\u{0346}', '\u{034A}',
+    '\u{034B}', '\u{034C}', '\u{0350}', '\u{0351}', '\u{0352}', '\u{0357}', '\u{035B}', '\u{0363}', '\u{0364}', '\u{0365}',
+    '\u{0366}', '\u{0367}', '\u{0368}', '\u{0369}', '\u{036A}', '\u{036B}', '\u{036C}', '\u{036D}', '\u{036E}', '\u{036F}',
+    '\u{0483}', '\u{0484}', '\u{0485}', '\u{0486}', '\u{0487}', '\u{0592}', '\u{0593}', '\u{0594}', '\u{0595}', '\u{0597}',
+    '\u{0598}', '\u{0599}', '\u{059C}', '\u{059D}', '\u{059E}', '\u{059F}', '\u{05A0}', '\u{05A1}', '\u{05A8}', '\u{05A9}',
+    '\u{05AB}', '\u{05AC}', '\u{05AF}', '\u{05C4}', '\u{0610}', '\u{0611}', '\u{0612}', '\u{0613}', '\u{0614}', '\u{0615}',
+    '\u{0616}', '\u{0617}', '\u{0657}', '\u{0658}', '\u{0659}', '\u{065A}', '\u{065B}', '\u{065D}', '\u{065E}', '\u{06D6}',
+    '\u{06D7}', '\u{06D8}', '\u{06D9}', '\u{06DA}', '\u{06DB}', '\u{06DC}', '\u{06DF}', '\u{06E0}', '\u{06E1}', '\u{06E2}',
+    '\u{06E4}', '\u{06E7}', '\u{06E8}', '\u{06EB}', '\u{06EC}', '\u{0730}', '\u{0732}', '\u{0733}', '\u{0735}', '\u{0736}',
+    '\u{073A}', '\u{073D}', '\u{073F}', '\u{0740}', '\u{0741}', '\u{0743}', '\u{0745}', '\u{0747}', '\u{0749}', '\u{074A}',
+    '\u{07EB}', '\u{07EC}', '\u{07ED}', '\u{07EE}', '\u{07EF}', '\u{07F0}', '\u{07F1}', '\u{07F3}', '\u{0816}', '\u{0817}',
+    '\u{0818}', '\u{0819}', '\u{081B}', '\u{081C}', '\u{081D}', '\u{081E}', '\u{081F}', '\u{0820}', '\u{0821}', '\u{0822}',
+    '\u{0823}', '\u{0825}', '\u{0826}', '\u{0827}', '\u{0829}', '\u{082A}', '\u{082B}', '\u{082C}', '\u{082D}', '\u{0951}',
+    '\u{0953}', '\u{0954}', '\u{0F82}', '\u{0F83}', '\u{0F86}', '\u{0F87}', '\u{135D}', '\u{135E}', '\u{135F}', '\u{17DD}',
+    '\u{193A}', '\u{1A17}', '\u{1A75}', '\u{1A76}', '\u{1A77}', '\u{1A78}', '\u{1A79}', '\u{1A7A}', '\u{1A7B}', '\u{1A7C}',
+    '\u{1B6B}', '\u{1B6D}', '\u{1B6E}', '\u{1B6F}', '\u{1B70}', '\u{1B71}', '\u{1B72}', '\u{1B73}', '\u{1CD0}', '\u{1CD1}',
+    '\u{1CD2}', '\u{1CDA}', '\u{1CDB}', '\u{1CE0}', '\u{1DC0}', '\u{1DC1}', '\u{1DC3}', '\u{1DC4}', '\u{1DC5}', '\u{1DC6}',
+    '\u{1DC7}', '\u{1DC8}', '\u{1DC9}', '\u{1DCB}', '\u{1DCC}', '\u{1DD1}', '\u{1DD2}', '\u{1DD3}', '\u{1DD4}', '\u{1DD5}',
+    '\u{1DD6}', '\u{1DD7}', '\u{1DD8}', '\u{1DD9}', '\u{1DDA}', '\u{1DDB}', '\u{1DDC}', '\u{1DDD}', '\u{1DDE}', '\u{1DDF}',
+    '\u{1DE0}', '\u{1DE1}', '\u{1DE2}', '\u{1DE3}', '\u{1DE4}', '\u{1DE5}', '\u{1DE6}', '\u{1DFE}', '\u{20D0}', '\u{20D1}',
+    '\u{20D4}', '\u{20D5}', '\u{20D6}', '\u{20D7}', '\u{20DB}', '\u{20DC}', '\u{20E1}', '\u{20E7}', '\u{20E9}', '\u{20F0}',
+    '\u{2CEF}', '\u{2CF0}', '\u{2CF1}', '\u{2DE0}', '\u{2DE1}', '\u{2DE2}', '\u{2DE3}', '\u{2DE4}', '\u{2DE5}', '\u{2DE6}',
+    '\u{2DE7}', '\u{2DE8}', '\u{2DE9}', '\u{2DEA}', '\u{2DEB}', '\u{2DEC}', '\u{2DED}', '\u{2DEE}', '\u{2DEF}', '\u{2DF0}',
+    '\u{2DF1}', '\u{2DF2}', '\u{2DF3}', '\u{2DF4}', '\u{2DF5}', '\u{2DF6}', '\u{2DF7}', '\u{2DF8}', '\u{2DF9}', '\u{2DFA}',
+    '\u{2DFB}', '\u{2DFC}', '\u{2DFD}', '\u{2DFE}', '\u{2DFF}', '\u{A66F}', '\u{A67C}', '\u{A67D}', '\u{A6F0}', '\u{A6F1}',
+    '\u{A8E0}', '\u{A8E1}', '\u{A8E2}', '\u{A8E3}', '\u{A8E4}', '\u{A8E5}', '\u{A8E6}', '\u{A8E7}', '\u{A8E8}', '\u{A8E9}',
+    '\u{A8EA}', '\u{A8EB}', '\u{A8EC}', '\u{A8ED}', '\u{A8EE}', '\u{A8EF}', '\u{A8F0}', '\u{A8F1}', '\u{AAB0}', '\u{AAB2}',
+    '\u{AAB3}', '\u{AAB7}', '\u{AAB8}', '\u{AABE}', '\u{AABF}', '\u{AAC1}', '\u{FE20}', '\u{FE21}', '\u{FE22}', '\u{FE23}',
+    '\u{FE24}', '\u{FE25}', '\u{FE26}', '\u{10A0F}', '\u{10A38}', '\u{1D185}', '\u{1D186}', '\u{1D187}', '\u{1D188}', '\u{1D189}',
+    '\u{1D1AA}', '\u{1D1AB}', '\u{1D1AC}', '\u{1D1AD}', '\u{1D242}', '\u{1D243}', '\u{1D244}',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(direct_color: bool) -> Database {
+        let mut database = Database::new();
+        database.name("xterm-test")
+            .raw("cup", b"\x1b[%i%p1%d;%p2%dH".as_slice())
+            .raw("cub", b"\x1b[%p1%dD".as_slice())
+            .raw("sgr0", b"\x1b[m".as_slice());
+
+        if direct_color {
+            database.raw("Tc", ());
+        }
+
+        database.build().unwrap()
+    }
+
+    fn written(command: impl Command, database: &Database, ctx: &mut Context) -> Vec<u8> {
+        let mut output = Vec::new();
+        command.write_to(database, ctx, &mut output).unwrap();
+        output
+    }
+
+    /// Splits written graphics commands into their control data and payloads
+    fn graphics_commands(output: &[u8]) -> Vec<(String, usize)> {
+        std::str::from_utf8(output).unwrap()
+            .split_terminator("\x1b\\")
+            .map(|command| {
+                let command = command.strip_prefix("\x1b_G").unwrap();
+                let (control, payload) = command.split_once(';').unwrap_or((command, ""));
+                (control.to_owned(), payload.len())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn transmitted_images_are_chunked() {
+        let database = database(false);
+        let mut ctx = Context::new();
+
+        // 3072 bytes encode to exactly one chunk of 4096
+        let png = [0; 3072];
+        assert_eq!(graphics_commands(&written(Transmit::png(1, &png), &database, &mut ctx)), [
+            ("a=t,f=100,i=1,q=2,m=0".to_owned(), 4096),
+        ]);
+
+        let png = [0; 3075];
+        assert_eq!(graphics_commands(&written(Transmit::png(1, &png), &database, &mut ctx)), [
+            ("a=t,f=100,i=1,q=2,m=1".to_owned(), 4096),
+            ("q=2,m=0".to_owned(), 4),
+        ]);
+
+        let png = [0; 3072 * 2];
+        assert_eq!(graphics_commands(&written(Transmit::png(1, &png), &database, &mut ctx)), [
+            ("a=t,f=100,i=1,q=2,m=1".to_owned(), 4096),
+            ("q=2,m=0".to_owned(), 4096),
+        ]);
+
+        assert_eq!(graphics_commands(&written(Transmit::png(1, &[]), &database, &mut ctx)), [
+            ("a=t,f=100,i=1,q=2,m=0".to_owned(), 0),
+        ]);
+    }
+
+    #[test]
+    fn id_colors() {
+        assert_eq!(id_color(38, 42), "\x1b[38;5;42m");
+        assert_eq!(id_color(58, 255), "\x1b[58;5;255m");
+        assert_eq!(id_color(38, 256), "\x1b[38;2;0;1;0m");
+        assert_eq!(id_color(38, 0x12345678), "\x1b[38;2;52;86;120m");
+    }
+
+    #[test]
+    fn placeholders() {
+        let database = database(false);
+        let mut ctx = Context::new();
+
+        let style = ContentStyle::new().foreground(crate::style::Color::Red);
+        let output = written(Placeholder::new(7, 2, 2).style(style), &database, &mut ctx);
+
+        let mut expected = "\x1b_Ga=p,U=1,i=7,c=2,r=2,q=2\x1b\\\x1b[38;5;7m".to_owned();
+        expected.extend([PLACEHOLDER, DIACRITICS[0], DIACRITICS[0], PLACEHOLDER, DIACRITICS[0], DIACRITICS[1]]);
+        expected.push_str("\x1bD\x1b[2D");
+        expected.extend([PLACEHOLDER, DIACRITICS[1], DIACRITICS[0], PLACEHOLDER, DIACRITICS[1], DIACRITICS[1]]);
+        expected.push_str(std::str::from_utf8(&written(style, &database, &mut ctx)).unwrap());
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn large_ids_need_direct_color() {
+        let mut ctx = Context::new();
+
+        for placeholder in [Placeholder::new(256, 1, 1), Placeholder::new(1, 1, 1).placement(256)] {
+            // COLORTERM says direct color is supported whatever the database has
+            if std::env::var_os("COLORTERM").is_none() {
+                assert!(!placeholder.is_supported(&database(false)));
+                assert!(placeholder.write_to(&database(false), &mut ctx, &mut Vec::new()).is_err());
+            }
+
+            let direct = database(true);
+            assert!(placeholder.is_supported(&direct));
+
+            let output = written(placeholder, &direct, &mut ctx);
+            assert!(String::from_utf8(output).unwrap().contains(";2;0;1;0m"));
+        }
+
+        assert!(Placeholder::new(255, 1, 1).placement(255).is_supported(&database(false)));
+    }
+
+    #[test]
+    fn non_ansi_terminals_are_unsupported() {
+        let mut database = Database::new();
+        database.name("vt52")
+            .raw("cup", b"\x1bY%p1%' '%+%c%p2%' '%+%c".as_slice())
+            .raw("cub1", b"\x1bD".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(!Place::new(1).is_supported(&database));
+        assert!(!Placeholder::new(1, 1, 1).is_supported(&database));
+    }
+
+    #[test]
+    fn commands_are_passed_through_tmux() {
+        let database = database(false);
+        let mut ctx = Context::new();
+        ctx.set_inside_tmux(true);
+
+        assert_eq!(written(DeleteImage(3), &database, &mut ctx), b"\x1bPtmux;\x1b\x1b_Ga=d,d=I,i=3,q=2\x1b\x1b\\\x1b\\");
+
+        ctx.set_inside_tmux(false);
+        assert_eq!(written(DeleteImage(3), &database, &mut ctx), b"\x1b_Ga=d,d=I,i=3,q=2\x1b\\");
+    }
+
+    #[test]
+    fn support_replies() {
+        assert_eq!(parse_support_reply(b"\x1b_Gi=31;OK\x1b\\"), Some(true));
+        assert_eq!(parse_support_reply(b"\x1b_Gi=31;OK\x07"), Some(true));
+        assert_eq!(parse_support_reply(b"\x1b_Gi=31;ENODATA:Insufficient image data\x1b\\"), Some(false));
+
+        for reply in [
+            b"\x1b_Gi=31;OK".as_slice(),
+            b"\x1b_Gi=32;OK\x1b\\",
+            b"\x1b_GOK\x1b\\",
+            b"\x1b[?62;4c",
+        ] {
+            assert_eq!(parse_support_reply(reply), None, "{reply:?}");
+        }
+    }
+}
@@ -0,0 +1,457 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Sixel graphics, from DEC's printers and terminals
+//!
+//! A sixel image is drawn in bands 6 pixels tall using a palette of colors, so an [Image] is
+//! reduced to the most important colors in it (with median cut) and dithered to hide the
+//! difference. Fully transparent pixels are left showing what was behind them.
+//!
+//! Terminals which support sixel graphics say so in their reply to primary device attributes,
+//! see [Terminal::supports_sixel](crate::Terminal::supports_sixel).
+
+use {
+    super::{fit, Image},
+
+    crate::{command::Context, Command, Capability},
+
+    std::io,
+
+    terminfo::Database,
+};
+
+/// Draws an image with sixel graphics at the cursor
+///
+/// The image is scaled to the given number of cells, using the size of a cell the OS reports for
+/// standard output (or standard input), whichever terminal the command is written to. This
+/// requires the `Sxl` capability, which [Terminal::probe](crate::Terminal::probe) adds to the
+/// database for terminals which support sixel graphics.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Sixel<'a> {
+    image: &'a Image,
+    columns: Option<u16>,
+    rows: Option<u16>,
+    colors: u16,
+    dither: bool,
+}
+
+impl<'a> Sixel<'a> {
+    pub const fn new(image: &'a Image) -> Self {
+        Self {
+            image,
+            columns: None,
+            rows: None,
+            colors: 256,
+            dither: true,
+        }
+    }
+
+    /// Scales the image to be `columns` cells wide. If the height isn't set, it is chosen to keep
+    /// the image's aspect ratio.
+    pub const fn columns(mut self, columns: u16) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Scales the image to be `rows` cells tall. If the width isn't set, it is chosen to keep the
+    /// image's aspect ratio.
+    pub const fn rows(mut self, rows: u16) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    /// Sets the most colors the palette can have, which is 256 by default. Some terminals only
+    /// have 16 color registers.
+    pub const fn colors(mut self, colors: u16) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Sets whether the image is dithered, which is on by default
+    pub const fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+}
+
+impl Command for Sixel<'_> {
+    fn size_hint(&self) -> Option<usize> {
+        let (width, height) = fit(self.image, self.columns, self.rows);
+        Some((width as usize * height as usize) / 3 + 64)
+    }
+
+    fn write_to(&self, database: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        if !self.is_supported(database) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "Sixel graphics are unsupported in this terminal"));
+        }
+
+        let (width, height) = fit(self.image, self.columns, self.rows);
+        let image = self.image.resize(width, height);
+
+        let palette = median_cut(&image, self.colors.clamp(1, 256) as usize);
+        let indices = map_to_palette(&image, &palette, self.dither);
+
+        target.write_all(&encode(&image, &palette, &indices))
+    }
+}
+
+impl Capability for Sixel<'_> {
+    fn is_supported(&self, database: &Database) -> bool {
+        database.raw("Sxl").is_some()
+    }
+}
+
+/// Pixels with less alpha than this are left transparent
+const OPAQUE: u8 = 128;
+
+/// A set of colors for median cut to split, with the number of pixels of each
+struct ColorBox {
+    colors: Vec<([u8; 3], u32)>,
+}
+
+impl ColorBox {
+    /// The channel with the largest range of values, and that range
+    fn widest_channel(&self) -> (usize, u8) {
+        (0..3)
+            .map(|channel| {
+                let values = self.colors.iter().map(|(color, _)| color[channel]);
+                let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                (channel, range)
+            })
+            .max_by_key(|(_, range)| *range)
+            .unwrap_or((0, 0))
+    }
+
+    /// The average of the colors, weighted by the number of pixels of each
+    fn average(&self) -> [u8; 3] {
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+
+        for (color, pixels) in &self.colors {
+            for channel in 0..3 {
+                sum[channel] += color[channel] as u64 * *pixels as u64;
+            }
+
+            count += *pixels as u64;
+        }
+
+        sum.map(|sum| (sum / count.max(1)) as u8)
+    }
+}
+
+/// Chooses a palette of at most `size` colors for `image`
+///
+/// The colors in the image are repeatedly split in half along their widest channel at the median
+/// pixel, and each of the resulting boxes of colors gives one color of the palette.
+fn median_cut(image: &Image, size: usize) -> Vec<[u8; 3]> {
+    // colors are counted with 5 bits per channel, which is plenty to choose a palette from
+    let mut counts = vec![0u32; 1 << 15];
+
+    for pixel in image.pixels().chunks_exact(4).filter(|pixel| pixel[3] >= OPAQUE) {
+        counts[color_key([pixel[0], pixel[1], pixel[2]])] += 1;
+    }
+
+    let colors: Vec<([u8; 3], u32)> = counts.iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(key, count)| {
+            let channel = |shift: usize| (((key >> shift) & 0x1F) * 255 / 31) as u8;
+            ([channel(10), channel(5), channel(0)], *count)
+        })
+        .collect();
+
+    if colors.is_empty() {
+        return vec![[0, 0, 0]];
+    }
+
+    let mut boxes = vec![ColorBox { colors }];
+
+    while boxes.len() < size {
+        // the box with the widest range of colors is split next
+        let Some((index, (channel, _))) = boxes.iter()
+            .map(ColorBox::widest_channel)
+            .enumerate()
+            .filter(|(index, (_, range))| *range > 0 && boxes[*index].colors.len() > 1)
+            .max_by_key(|(_, (_, range))| *range)
+        else {
+            break;
+        };
+
+        let mut colors = std::mem::take(&mut boxes[index].colors);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+
+        let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+        let mut seen = 0;
+
+        let median = colors.iter()
+            .position(|(_, count)| {
+                seen += *count as u64;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+
+        // both halves have at least one color
+        let split = (median + 1).clamp(1, colors.len() - 1);
+
+        boxes[index].colors = colors.split_off(split);
+        boxes.push(ColorBox { colors });
+    }
+
+    boxes.iter().map(ColorBox::average).collect()
+}
+
+fn color_key([r, g, b]: [u8; 3]) -> usize {
+    (r as usize >> 3) << 10 | (g as usize >> 3) << 5 | b as usize >> 3
+}
+
+/// Returns the index in `palette` of the color for each pixel of `image`, or `None` for pixels
+/// which are transparent
+///
+/// With `dither`, the difference between each pixel and the color chosen for it is spread to the
+/// pixels after it (Floyd-Steinberg dithering).
+fn map_to_palette(image: &Image, palette: &[[u8; 3]], dither: bool) -> Vec<Option<u8>> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    // the closest color is remembered for each color with 5 bits per channel
+    let mut closest_cache = vec![u16::MAX; 1 << 15];
+
+    let mut closest = |color: [u8; 3]| {
+        let key = color_key(color);
+
+        if closest_cache[key] == u16::MAX {
+            let distance = |entry: &[u8; 3]| -> i32 {
+                (0..3).map(|channel| (entry[channel] as i32 - color[channel] as i32).pow(2)).sum()
+            };
+
+            closest_cache[key] = (0..palette.len()).min_by_key(|index| distance(&palette[*index])).unwrap_or(0) as u16;
+        }
+
+        closest_cache[key] as u8
+    };
+
+    // the error carried to each pixel in the current and next rows
+    let mut errors = vec![[0i32; 3]; width * 2 + 2];
+    let mut indices = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        let (current, next) = errors.split_at_mut(width + 1);
+        next.fill([0; 3]);
+
+        for x in 0..width {
+            let pixel = image.pixel(x as u32, y as u32);
+
+            if pixel[3] < OPAQUE {
+                indices.push(None);
+                continue;
+            }
+
+            let wanted: [i32; 3] = std::array::from_fn(|channel| pixel[channel] as i32 + current[x][channel] / 16);
+            let index = closest(wanted.map(|value| value.clamp(0, 255) as u8));
+            indices.push(Some(index));
+
+            if !dither {
+                continue;
+            }
+
+            let chosen = palette[index as usize];
+
+            for channel in 0..3 {
+                let error = wanted[channel] - chosen[channel] as i32;
+
+                current[x + 1][channel] += error * 7;
+                next[(x + width) % (width + 1)][channel] += error * 3;
+                next[x][channel] += error * 5;
+                next[x + 1][channel] += error;
+            }
+        }
+
+        // the next row becomes the current one
+        let (current, next) = errors.split_at_mut(width + 1);
+        current.copy_from_slice(next);
+    }
+
+    indices
+}
+
+/// Writes the sixel sequence for `image` with the colors of `palette` chosen for each pixel
+fn encode(image: &Image, palette: &[[u8; 3]], indices: &[Option<u8>]) -> Vec<u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    // the second parameter (1) leaves pixels which aren't drawn showing what was behind them, and
+    // the raster attributes give a 1:1 aspect ratio and the size
+    let mut output = format!("\x1bP0;1;0q\"1;1;{width};{height}").into_bytes();
+
+    for (index, [r, g, b]) in palette.iter().enumerate() {
+        // colors are given as percentages
+        let percent = |channel: u8| channel as u32 * 100 / 255;
+        output.extend(format!("#{index};2;{};{};{}", percent(*r), percent(*g), percent(*b)).bytes());
+    }
+
+    let mut band = vec![0u8; width];
+
+    for band_start in (0..height).step_by(6) {
+        let band_end = (band_start + 6).min(height);
+
+        let mut used = vec![false; palette.len()];
+
+        for y in band_start..band_end {
+            for index in indices[y * width..(y + 1) * width].iter().flatten() {
+                used[*index as usize] = true;
+            }
+        }
+
+        let mut first = true;
+
+        for color in (0..palette.len()).filter(|color| used[*color]) {
+            // each sixel is a column of 6 pixels, with a bit for each pixel of this color
+            band.fill(0);
+
+            for y in band_start..band_end {
+                for (x, index) in indices[y * width..(y + 1) * width].iter().enumerate() {
+                    if *index == Some(color as u8) {
+                        band[x] |= 1 << (y - band_start);
+                    }
+                }
+            }
+
+            // colors after the first are drawn over the same band from its start
+            if !first {
+                output.push(b'$');
+            }
+
+            first = false;
+
+            output.extend(format!("#{color}").bytes());
+            write_run_lengths(&band, &mut output);
+        }
+
+        output.push(b'-');
+    }
+
+    output.extend_from_slice(b"\x1b\\");
+    output
+}
+
+/// Writes the sixels in `band`, writing repeated sixels with a repeat count
+fn write_run_lengths(band: &[u8], output: &mut Vec<u8>) {
+    // trailing empty sixels don't need to be written
+    let length = band.iter().rposition(|sixel| *sixel != 0).map_or(0, |last| last + 1);
+
+    let mut index = 0;
+
+    while index < length {
+        let sixel = band[index];
+        let run = band[index..length].iter().take_while(|other| **other == sixel).count();
+
+        match run {
+            1..=3 => output.extend(std::iter::repeat_n(sixel + 63, run)),
+            _ => output.extend(format!("!{run}{}", (sixel + 63) as char).bytes()),
+        }
+
+        index += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba_image(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Image {
+        let pixel = &pixel;
+        let pixels = (0..height).flat_map(|y| (0..width).flat_map(move |x| pixel(x, y))).collect();
+        Image::from_rgba(width, height, pixels).unwrap()
+    }
+
+    /// Returns the sixel data after the raster attributes and color definitions
+    fn sixels(output: &[u8], palette: &[[u8; 3]]) -> String {
+        let output = std::str::from_utf8(output).unwrap().strip_suffix("\x1b\\").unwrap();
+
+        let percent = |channel: u8| channel as u32 * 100 / 255;
+        let [r, g, b] = palette[palette.len() - 1];
+        let last_definition = format!("#{};2;{};{};{}", palette.len() - 1, percent(r), percent(g), percent(b));
+
+        let (_, data) = output.split_once(&last_definition).unwrap();
+        data.to_owned()
+    }
+
+    #[test]
+    fn single_color() {
+        let image = rgba_image(2, 6, |_, _| [255, 0, 0, 255]);
+        let palette = median_cut(&image, 256);
+
+        assert_eq!(palette, [[255, 0, 0]]);
+
+        let indices = map_to_palette(&image, &palette, true);
+        assert!(indices.iter().all(|index| *index == Some(0)));
+
+        let output = encode(&image, &palette, &indices);
+        assert_eq!(output, b"\x1bP0;1;0q\"1;1;2;6#0;2;100;0;0#0~~-\x1b\\");
+    }
+
+    #[test]
+    fn transparent_pixels_are_not_drawn() {
+        // the left column is transparent and so is the top half of the right one
+        let image = rgba_image(2, 6, |x, y| if x == 0 || y < 3 { [0, 0, 0, 0] } else { [0, 0, 255, 255] });
+        let palette = median_cut(&image, 256);
+        let indices = map_to_palette(&image, &palette, true);
+
+        assert_eq!(indices.iter().filter(|index| index.is_none()).count(), 9);
+        assert_eq!(sixels(&encode(&image, &palette, &indices), &palette), "#0?w-");
+
+        // an image with no opaque pixels draws nothing
+        let image = rgba_image(3, 6, |_, _| [255, 255, 255, 0]);
+        let palette = median_cut(&image, 256);
+        let indices = map_to_palette(&image, &palette, true);
+
+        assert_eq!(encode(&image, &palette, &indices), b"\x1bP0;1;0q\"1;1;3;6#0;2;0;0;0-\x1b\\");
+    }
+
+    #[test]
+    fn run_lengths() {
+        let mut output = Vec::new();
+        write_run_lengths(&[63, 63, 63], &mut output);
+        assert_eq!(output, b"~~~");
+
+        let mut output = Vec::new();
+        write_run_lengths(&[63, 63, 63, 63, 1, 0, 0, 1, 0, 0], &mut output);
+        assert_eq!(output, b"!4~@??@");
+
+        let mut output = Vec::new();
+        write_run_lengths(&[0; 8], &mut output);
+        assert_eq!(output, b"");
+    }
+
+    #[test]
+    fn bands_of_partial_height() {
+        // 8 rows make a full band and one of 2 rows, alternating between black and white
+        let image = rgba_image(1, 8, |_, y| if y % 2 == 0 { [0, 0, 0, 255] } else { [255, 255, 255, 255] });
+        let palette = median_cut(&image, 2);
+        let indices = map_to_palette(&image, &palette, false);
+
+        let black = palette.iter().position(|color| *color == [0, 0, 0]).unwrap();
+        let white = 1 - black;
+
+        // the black rows are bits 0, 2 and 4 of the first band and bit 0 of the second
+        let expected = match black {
+            0 => format!("#{black}T$#{white}i-#{black}@$#{white}A-"),
+            _ => format!("#{white}i$#{black}T-#{white}A$#{black}@-"),
+        };
+
+        assert_eq!(sixels(&encode(&image, &palette, &indices), &palette), expected);
+    }
+}
@@ -16,17 +16,36 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>
 */
 
-use std::io;
-use terminfo::{Database, expand::Context};
+use std::{collections::BTreeMap, io};
+use terminfo::Database;
 
+pub mod clipboard;
 pub mod command;
+pub mod cursor;
+pub mod editor;
+pub mod event;
+pub mod features;
+pub mod hyperlink;
+pub mod image;
+pub mod line_drawing;
 pub mod style;
+pub mod table;
 pub mod misc;
+pub mod palette;
+pub mod progress;
+pub mod prompt;
+pub mod shell;
+pub mod text;
 
+mod base64;
 mod define_macro;
+mod sys;
 
 pub use {
     command::{Command, Capability},
+    event::Event,
+    features::TerminalFeatures,
+    sys::WindowSize,
     terminfo
 };
 
@@ -37,7 +56,18 @@ pub struct Terminal<I: io::Read, O: io::Write> {
     reader: I,
     writer: O,
     info: Database,
-    terminfo_ctx: Context,
+    /// the database loaded from `TERM`, which `info` is rebuilt from when the terminal is probed
+    loaded_info: Database,
+    /// the capabilities the terminal reported when probed, which take precedence over `loaded_info`
+    probed: BTreeMap<String, Option<terminfo::Value>>,
+    terminfo_ctx: command::Context,
+    decoder: event::Decoder,
+    /// whether the terminal supports synchronized output, once it has been asked
+    synchronized_output: Option<bool>,
+    /// whether the terminal supports sixel graphics, once it has been asked
+    sixel_graphics: Option<bool>,
+    /// whether the terminal supports the kitty graphics protocol, once it has been asked
+    kitty_graphics: Option<bool>,
 }
 
 impl<'a, 'b> Default for Terminal<io::StdinLock<'a>, io::StdoutLock<'b>> {
@@ -66,22 +96,68 @@ impl<I: io::Read, O: io::Write> Terminal<I, O> {
     /// Creates a new `Terminal` instance which can be used to queue commands
     #[inline]
     pub fn new(reader: I, writer: O) -> Result<Self, io::Error> {
+        let info = match Database::from_env() {
+            Ok(info) => info,
+            Err(error) => match error {
+                terminfo::Error::Io(io_err) => return Err(io_err),
+                terminfo::Error::Expand(_) => panic!("there should not be an expansion error when creating a database, right?"),
+                terminfo::Error::NotFound => panic!("if the database is not found, then this device is probably (currently) unsupported"),
+                terminfo::Error::Parse => return Err(io::Error::new(io::ErrorKind::InvalidData, "error parsing the data in the database, although, I didn't think any parsing would happen during database creation."))
+            },
+        };
+
+        // tmux sets TMUX for the programs it runs, which is checked once here rather than each
+        // time a command has to be passed through
+        let mut terminfo_ctx = command::Context::new();
+        terminfo_ctx.set_inside_tmux(std::env::var_os("TMUX").is_some());
+        terminfo_ctx.set_notification_protocol(misc::NotificationProtocol::detect_with_env(&info));
+
         Ok(Self {
             reader,
             writer,
-            info: match Database::from_env() {
-                Ok(info) => info,
-                Err(error) => match error {
-                    terminfo::Error::Io(io_err) => return Err(io_err),
-                    terminfo::Error::Expand(_) => panic!("there should not be an expansion error when creating a database, right?"),
-                    terminfo::Error::NotFound => panic!("if the database is not found, then this device is probably (currently) unsupported"),
-                    terminfo::Error::Parse => return Err(io::Error::new(io::ErrorKind::InvalidData, "error parsing the data in the database, although, I didn't think any parsing would happen during database creation."))
-                },
-            },
-            terminfo_ctx: Context::default()
+            decoder: event::Decoder::new(&info),
+            loaded_info: info.clone(),
+            info,
+            probed: BTreeMap::new(),
+            terminfo_ctx,
+            synchronized_output: None,
+            sixel_graphics: None,
+            kitty_graphics: None,
         })
     }
     
+    /// The terminfo database for this terminal, which commands are written with
+    ///
+    /// Once the terminal has been [probed](Self::probe), this only has the capabilities it
+    /// reported along with the standard capabilities and the user defined ones this crate uses.
+    /// [capability](Self::capability) finds any capability.
+    pub fn database(&self) -> &Database {
+        &self.info
+    }
+
+    /// Returns the value of the terminfo capability `name`
+    ///
+    /// The terminal's own value is used if it reported one when it was [probed](Self::probe), and
+    /// otherwise the value in the terminfo database loaded from `TERM`.
+    pub fn capability(&self, name: &str) -> Option<&terminfo::Value> {
+        let name = terminfo::names::ALIASES.get(name).copied().unwrap_or(name);
+
+        match self.probed.get(name) {
+            Some(value) => value.as_ref(),
+            None => self.loaded_info.raw(name),
+        }
+    }
+
+    /// The reader input is read from
+    pub fn reader(&self) -> &I {
+        &self.reader
+    }
+
+    /// The writer commands are written to
+    pub fn writer(&self) -> &O {
+        &self.writer
+    }
+
     /// Consumes `self` and returns the reader and writer used under the hood
     pub fn into_inner(self) -> (I, O) {
         (
@@ -105,7 +181,7 @@ impl<I: io::Read, O: io::Write> Terminal<I, O> {
     /// based on that.
     pub fn queue_if_supported(&mut self, cmd: impl Command) -> Option<io::Result<()>> {
         match cmd.is_supported(&self.info) {
-            true => Some(cmd.write_to(&self.info, &mut self.terminfo_ctx, &mut self.writer)),
+            true => Some(self.write_command(&cmd)),
             false => None,
         }
     }
@@ -115,15 +191,571 @@ impl<I: io::Read, O: io::Write> Terminal<I, O> {
     /// This function may not immediately execute the command. Call `flush()` after to execute all 
     /// queued commands
     pub fn queue(&mut self, command: impl Command) -> io::Result<()> {
-        command.write_to(&self.info, &mut self.terminfo_ctx, &mut self.writer)
+        self.write_command(&command)
     }
 
     pub fn queue_all<const N: usize>(&mut self, commands: [&dyn Command; N]) -> io::Result<()> {
 
         for cmd in commands {
-            cmd.write_to(&self.info, &mut self.terminfo_ctx, &mut self.writer, )?;
+            self.write_command(cmd)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_command(&mut self, command: &dyn Command) -> io::Result<()> {
+        command.write_to(&self.info, &mut self.terminfo_ctx, &mut self.writer)
+    }
+
+    /// Sets the baud rate padding in terminfo capabilities is worked out for, see
+    /// [Context::baud_rate](command::Context::baud_rate)
+    pub fn set_baud_rate(&mut self, baud_rate: u32) {
+        self.terminfo_ctx.set_baud_rate(baud_rate);
+    }
+
+    /// The baud rate padding in terminfo capabilities is worked out for, which is 38400 unless it
+    /// has been [set](Self::set_baud_rate)
+    pub fn baud_rate(&self) -> u32 {
+        self.terminfo_ctx.baud_rate()
+    }
+
+    /// Sets the palette [Color::Rgb](style::Color::Rgb) is matched against on terminals which
+    /// don't support direct color, without changing the terminal's colors. [palette](Self::palette)
+    /// sets it to the terminal's palette. Defaults to [Palette::XTERM](palette::Palette::XTERM).
+    pub fn use_palette(&mut self, palette: palette::Palette) {
+        self.terminfo_ctx.use_palette(palette);
+    }
+
+    /// Queues every command in `commands` in order, stopping at the first error
+    ///
+    /// This is for lists of commands built as the program runs. Commands of different types can
+    /// be given as `Box<dyn Command>`, and a fixed group of them can be combined into one command
+    /// as a tuple.
+    pub fn queue_iter(&mut self, commands: impl IntoIterator<Item = impl Command>) -> io::Result<()> {
+        for command in commands {
+            self.queue(command)?;
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Reads the next [Event] from the reader, blocking until there is one
+    ///
+    /// The terminal should be in [raw mode](RawMode) so that input is received as soon as it is
+    /// typed. Returns an error with an `ErrorKind` of `UnexpectedEof` if the reader has no more
+    /// input.
+    pub fn read_event(&mut self) -> io::Result<Event> {
+        loop {
+            if let Some(event) = self.decoder.next() {
+                return Ok(event);
+            }
+
+            if self.fill_decoder()? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "there is no more input to read events from"));
+            }
+        }
+    }
+
+    /// Reads input into the decoder, returning the number of bytes read
+    fn fill_decoder(&mut self) -> io::Result<usize> {
+        // at least as large as the buffer of `Stdin`, so that input is never left in that buffer
+        // where `query` can't see it
+        let mut buffer = [0; 8192];
+
+        let read = self.reader.read(&mut buffer)?;
+        self.decoder.push(&buffer[..read]);
+
+        Ok(read)
+    }
+
+    /// Sets the most bytes of text kept from a single [paste](Event::Paste), which is 1 MiB by
+    /// default. Anything pasted past the limit is read and thrown away.
+    pub fn set_paste_limit(&mut self, limit: usize) {
+        self.decoder.paste_limit = limit;
+    }
+
+    /// Returns the size of the terminal as `(columns, rows)`
+    ///
+    /// The size is asked for from the OS, and if that fails, the `COLUMNS` and `LINES` environment
+    /// variables and then the terminfo database are used.
+    pub fn size(&self) -> io::Result<(u16, u16)> {
+        if let Ok(size) = sys::window_size() && size.columns > 0 && size.rows > 0 {
+            return Ok((size.columns, size.rows));
+        }
+
+        let from_env = |name| std::env::var(name).ok()?.parse::<u16>().ok().filter(|value| *value > 0);
+
+        let columns = from_env("COLUMNS")
+            .or_else(|| self.info.get::<terminfo::capability::Columns>().map(|columns| columns.0 as u16));
+        let rows = from_env("LINES")
+            .or_else(|| self.info.get::<terminfo::capability::Lines>().map(|lines| lines.0 as u16));
+
+        match (columns, rows) {
+            (Some(columns), Some(rows)) => Ok((columns, rows)),
+            _ => Err(io::Error::new(io::ErrorKind::Unsupported, "the size of the terminal could not be found")),
+        }
+    }
+
+    /// Returns the size of the terminal window in cells and pixels as reported by the OS
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        sys::window_size()
+    }
+
+    /// Sets the shape of the cursor, see [SetCursorStyle](cursor::SetCursorStyle)
+    ///
+    /// The returned guard puts back the user's cursor shape when it is dropped, including when the
+    /// program exits early with an error. The style can be changed again through the guard, such as
+    /// when an editor switches modes.
+    pub fn set_cursor_style(&mut self, shape: cursor::CursorShape, blinking: bool) -> io::Result<cursor::CursorStyleGuard<'_, I, O>> {
+        let mut guard = RestoreGuard::new(self, cursor::ResetCursorStyle);
+
+        guard.queue(cursor::SetCursorStyle(shape, blinking))?;
+        io::Write::flush(&mut *guard)?;
+        Ok(guard)
+    }
+
+    /// Changes the colors the terminal shows for the ids in `colors`, see
+    /// [SetPaletteColor](palette::SetPaletteColor)
+    ///
+    /// The returned guard puts the terminal's palette back when it is dropped.
+    pub fn set_palette(&mut self, colors: &[(u8, style::Color)]) -> io::Result<palette::PaletteGuard<'_, I, O>> {
+        let mut guard = RestoreGuard::new(self, palette::ResetPalette);
+
+        for (id, color) in colors {
+            guard.queue(palette::SetPaletteColor(*id, *color))?;
+        }
+
+        io::Write::flush(&mut *guard)?;
+        Ok(guard)
+    }
+}
+
+impl<I: io::Read + PollInput + AsTty, O: io::Write> Terminal<I, O> {
+    /// Writes `request` and waits up to `timeout` for the terminal to reply
+    ///
+    /// `parse` is given each escape sequence received, including the leading escape, and should
+    /// return `Some` once it is given the reply. Everything else received while waiting, such as
+    /// keys typed at the same time, is kept to be read by [read_event](Self::read_event).
+    ///
+    /// Returns `Ok(None)` if there is no reply in time, which is common for terminals which don't
+    /// understand the request. Raw mode is enabled while waiting, so that the reply isn't held back
+    /// until a line has been typed.
+    pub fn query<T>(&mut self, request: impl Command, parse: impl FnMut(&[u8]) -> Option<T>, timeout: std::time::Duration) -> io::Result<Option<T>> {
+
+        let raw_mode = RawMode::enable(&self.reader)?;
+        let reply = self.query_in_raw_mode(request, parse, timeout);
+        raw_mode.disable()?;
+
+        reply
+    }
+
+    /// Like [query](Self::query), but raw mode must already be enabled
+    ///
+    /// Exchanges made of several queries enable raw mode once around all of them, so that replies
+    /// which arrive between two queries aren't echoed.
+    fn query_in_raw_mode<T>(&mut self, request: impl Command, mut parse: impl FnMut(&[u8]) -> Option<T>, timeout: std::time::Duration) -> io::Result<Option<T>> {
+
+        self.queue(request)?;
+        io::Write::flush(&mut self.writer)?;
+
+        let deadline = std::time::Instant::now() + timeout;
+
+        let reply = loop {
+            if let Some(reply) = self.decoder.take_response(&mut parse) {
+                break Some(reply);
+            }
+
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+
+            if !self.reader.poll_input(remaining)? || self.fill_decoder()? == 0 {
+                break None;
+            }
+        };
+
+        Ok(reply)
+    }
+
+    /// Returns the position of the cursor as `(column, row)`, counting from 0
+    ///
+    /// The terminal is asked with [RequestCursorPosition](cursor::RequestCursorPosition). Returns an
+    /// error with an `ErrorKind` of `TimedOut` if it doesn't reply within a second.
+    pub fn cursor_position(&mut self) -> io::Result<(u16, u16)> {
+        let one_based = cursor::position_report_is_one_based(&self.info);
+
+        self.query(cursor::RequestCursorPosition, |reply| cursor::parse_position_report(reply, one_based), std::time::Duration::from_secs(1))?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "the terminal didn't report the cursor position"))
+    }
+
+    /// Asks the terminal what it supports (see [features]), waiting up to a second for it to reply,
+    /// and merges the capabilities it reports into the terminfo database used for commands
+    ///
+    /// The capabilities in [DEFAULT_CAPABILITIES](features::DEFAULT_CAPABILITIES) are asked for.
+    pub fn probe(&mut self) -> io::Result<TerminalFeatures> {
+        self.probe_capabilities(features::DEFAULT_CAPABILITIES)
+    }
+
+    /// Like [probe](Self::probe), but asks for the terminfo capabilities named in `capabilities`
+    pub fn probe_capabilities(&mut self, capabilities: &[&str]) -> io::Result<TerminalFeatures> {
+        let request = features::probe_request(capabilities);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(1);
+
+        let mut features = TerminalFeatures::default();
+        let mut request = request.as_slice();
+
+        let raw_mode = RawMode::enable(&self.reader)?;
+
+        // the reply to the device attributes request, which is written last, means every other
+        // request has been answered if it is going to be
+        while !features.responded() {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+
+            match self.query_in_raw_mode(style::Write(request), features::parse_reply, remaining)? {
+                Some(reply) => features.add(reply),
+                None => break,
+            }
+
+            request = &[];
+        }
+
+        raw_mode.disable()?;
+
+        self.probed.extend(features.overrides());
+        self.info = features::merge(&self.loaded_info, &self.probed);
+        self.decoder.load_sequences(&self.info);
+
+        // a terminal which doesn't reply to device attributes can't say it supports sixel graphics
+        self.sixel_graphics = Some(features.sixel());
+
+        // tmux answers XTVERSION itself, so the reply says for certain whether commands go to it
+        if let Some(name) = &features.name {
+            self.terminfo_ctx.set_inside_tmux(name == "tmux");
+        }
+
+        // the terminal's name may only be known now, from `TN`
+        self.terminfo_ctx.set_notification_protocol(misc::NotificationProtocol::detect_with_env(&self.info));
+
+        Ok(features)
+    }
+
+    /// Returns true if the terminal supports sixel graphics, so that [Sixel](image::sixel::Sixel)
+    /// images can be written
+    ///
+    /// Unless the terminfo database has the `Sxl` capability or the terminal has been probed
+    /// already, the terminal is asked for its device attributes, which list sixel graphics. If it
+    /// doesn't reply, sixel graphics are taken to be unsupported and it isn't asked again.
+    pub fn supports_sixel(&mut self) -> io::Result<bool> {
+        if self.info.raw("Sxl").is_some() {
+            return Ok(true);
+        }
+
+        match self.sixel_graphics {
+            Some(supported) => Ok(supported),
+            None => Ok(self.probe_capabilities(&[])?.sixel()),
+        }
+    }
+
+    /// Returns true if the terminal supports the kitty graphics protocol, see [image::kitty]
+    ///
+    /// The first time this is called, the terminal is asked to load a tiny image without keeping
+    /// it. tmux doesn't pass the reply on, so this is false inside tmux even when the terminal
+    /// outside it supports the protocol.
+    pub fn supports_kitty_graphics(&mut self) -> io::Result<bool> {
+        if let Some(supported) = self.kitty_graphics {
+            return Ok(supported);
+        }
+
+        let supported = self.collect_replies(style::Write(image::kitty::SUPPORT_QUERY), image::kitty::parse_support_reply)?.contains(&true);
+
+        self.kitty_graphics = Some(supported);
+        Ok(supported)
+    }
+
+    /// Returns the terminal's default foreground color, or `None` if it doesn't report it
+    ///
+    /// The terminal is asked with [RequestForegroundColor](style::RequestForegroundColor).
+    pub fn foreground_color(&mut self) -> io::Result<Option<style::Color>> {
+        let parse = |reply: &[u8]| style::parse_color_report(reply, 10).map(|color| (0, color));
+        Ok(self.query_colors([style::RequestForegroundColor], parse, 1)?[0])
+    }
+
+    /// Returns the terminal's default background color, or `None` if it doesn't report it
+    ///
+    /// The terminal is asked with [RequestBackgroundColor](style::RequestBackgroundColor). See
+    /// [Theme::detect](style::Theme::detect) to find out if the background is light or dark.
+    pub fn background_color(&mut self) -> io::Result<Option<style::Color>> {
+        let parse = |reply: &[u8]| style::parse_color_report(reply, 11).map(|color| (0, color));
+        Ok(self.query_colors([style::RequestBackgroundColor], parse, 1)?[0])
+    }
+
+    /// Returns the colors the terminal shows for each id in `ids`, or `None` for the ones it doesn't
+    /// report
+    ///
+    /// The terminal is asked with [RequestPaletteColor](palette::RequestPaletteColor).
+    pub fn palette_colors(&mut self, ids: &[u8]) -> io::Result<Vec<Option<style::Color>>> {
+        let parse = |reply: &[u8]| {
+            let (id, color) = palette::parse_palette_report(reply)?;
+            Some((ids.iter().position(|requested| *requested == id)?, color))
+        };
+
+        self.query_colors(ids.iter().map(|id| palette::RequestPaletteColor(*id)), parse, ids.len())
+    }
+
+    /// Returns the terminal's palette, asking it for each color it supports. Colors it doesn't
+    /// report are taken from [Palette::XTERM](palette::Palette::XTERM).
+    ///
+    /// The palette is kept, and [Color::Rgb](style::Color::Rgb) is matched against it from then
+    /// on if the terminal doesn't support direct color.
+    pub fn palette(&mut self) -> io::Result<palette::Palette> {
+        let colors = self.info.get::<terminfo::capability::MaxColors>().map_or(0, |colors| colors.0.clamp(0, 256));
+        let ids: Vec<u8> = (0..colors).map(|id| id as u8).collect();
+
+        let mut palette = palette::Palette::XTERM;
+
+        for (id, color) in ids.iter().zip(self.palette_colors(&ids)?) {
+            if let Some(color) = color {
+                palette.set(*id, color.to_rgb());
+            }
+        }
+
+        self.terminfo_ctx.use_palette(palette);
+        Ok(palette)
+    }
+
+    /// Calls `f`, wrapped in [BeginSynchronizedUpdate](misc::BeginSynchronizedUpdate) and
+    /// [EndSynchronizedUpdate](misc::EndSynchronizedUpdate) so that everything it queues is shown at
+    /// once, and then flushes
+    ///
+    /// The first time this is called, the terminal is asked whether it supports synchronized output
+    /// (with DECRQM) unless the terminfo database says it does. On terminals which don't, `f` is
+    /// just called.
+    pub fn synchronized<T>(&mut self, f: impl FnOnce(&mut Self) -> io::Result<T>) -> io::Result<T> {
+        let supported = match self.synchronized_output {
+            Some(supported) => supported,
+            None => {
+                let supported = match self.info.raw("Sync") {
+                    Some(_) => true,
+                    None => {
+                        self.collect_replies(style::Write(b"\x1b[?2026$p"), |reply| misc::parse_mode_report(reply, 2026))?.contains(&true)
+                    }
+                };
+
+                self.synchronized_output = Some(supported);
+                supported
+            }
+        };
+
+        if supported {
+            self.queue(misc::BeginSynchronizedUpdate)?;
+        }
+
+        let result = f(self);
+
+        // the update is ended even if `f` failed, so the terminal doesn't wait for it
+        if supported {
+            self.queue(misc::EndSynchronizedUpdate)?;
+        }
+
+        io::Write::flush(&mut self.writer)?;
+        result
+    }
+
+    /// Returns the contents of `selection`, or `None` if the terminal doesn't report it
+    ///
+    /// The terminal is asked with [RequestClipboard](clipboard::RequestClipboard). Many terminals
+    /// don't allow programs to read the clipboard, in which case they either don't reply or reply
+    /// with nothing, and both give `None`. Data which isn't valid UTF-8 is converted lossily.
+    pub fn read_clipboard(&mut self, selection: clipboard::Selection) -> io::Result<Option<String>> {
+        let data = self.collect_replies(clipboard::RequestClipboard(selection), clipboard::parse_clipboard_report)?.into_iter().next().flatten();
+
+        Ok(data.map(|data| String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    /// Writes `requests` for colors and collects the `count` colors `parse` finds in the replies,
+    /// which it gives along with their index
+    fn query_colors<C: Command>(
+        &mut self,
+        requests: impl IntoIterator<Item = C>,
+        parse: impl FnMut(&[u8]) -> Option<(usize, style::Color)>,
+        count: usize,
+    ) -> io::Result<Vec<Option<style::Color>>> {
+        let requests: Vec<C> = requests.into_iter().collect();
+        let mut colors = vec![None; count];
+
+        for (index, color) in self.collect_replies(requests, parse)? {
+            colors[index] = Some(color);
+        }
+
+        Ok(colors)
+    }
+
+    /// Writes `requests` and collects the replies `parse` finds to them
+    ///
+    /// Device attributes are asked for afterwards, which nearly every terminal replies to, so
+    /// terminals which don't reply to the other requests don't have to be waited on for long.
+    fn collect_replies<T>(&mut self, requests: impl Command, mut parse: impl FnMut(&[u8]) -> Option<T>) -> io::Result<Vec<T>> {
+        enum Reply<T> {
+            Reply(T),
+            DeviceAttributes,
+        }
+
+        let mut parse = |reply: &[u8]| match parse(reply) {
+            Some(reply) => Some(Reply::Reply(reply)),
+            None => matches!(features::parse_reply(reply), Some(features::Reply::DeviceAttributes(_)))
+                .then_some(Reply::DeviceAttributes),
+        };
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(1);
+
+        let mut request: &[u8] = b"\x1b[c";
+        let mut replies = Vec::new();
+
+        let raw_mode = RawMode::enable(&self.reader)?;
+        self.queue(requests)?;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+
+            match self.query_in_raw_mode(style::Write(request), &mut parse, remaining)? {
+                Some(Reply::Reply(reply)) => replies.push(reply),
+                Some(Reply::DeviceAttributes) | None => break,
+            }
+
+            request = &[];
+        }
+
+        raw_mode.disable()?;
+
+        Ok(replies)
+    }
+}
+
+/// A reader which can be waited on until it has input, which [Terminal::query] needs
+///
+/// This is implemented for everything with a file descriptor, such as standard input, on unix.
+pub trait PollInput {
+    /// Waits until there is input to read, returning false if `timeout` passes first
+    fn poll_input(&self, timeout: std::time::Duration) -> io::Result<bool>;
+}
+
+#[cfg(unix)]
+impl<T: std::os::fd::AsFd> PollInput for T {
+    fn poll_input(&self, timeout: std::time::Duration) -> io::Result<bool> {
+        sys::wait_for_input(self.as_fd(), timeout)
+    }
+}
+
+/// A reader or writer which may be connected to a terminal
+///
+/// [RawMode] changes the settings of the terminal a reader is connected to, and progress bars
+/// only redraw themselves on writers which are terminals. This is implemented for everything with
+/// a file descriptor, such as standard input and output, on unix.
+pub trait AsTty {
+    /// Returns true if this is connected to a terminal
+    fn is_tty(&self) -> bool;
+
+    /// Puts the terminal this is connected to into raw mode, see [RawMode::enable]
+    fn enable_raw_mode(&self) -> io::Result<RawMode>;
+}
+
+#[cfg(unix)]
+impl<T: std::os::fd::AsFd> AsTty for T {
+    fn is_tty(&self) -> bool {
+        sys::is_tty(self.as_fd())
+    }
+
+    fn enable_raw_mode(&self) -> io::Result<RawMode> {
+        Ok(RawMode {
+            original: sys::enable_raw_mode(self.as_fd())?,
+        })
+    }
+}
+
+/// Queues a command which undoes a change to the terminal when it is dropped, such as
+/// [ResetPalette](palette::ResetPalette) after [Terminal::set_palette]
+///
+/// The terminal can still be used through the guard.
+pub struct RestoreGuard<'a, I: io::Read, O: io::Write, C: Command> {
+    terminal: &'a mut Terminal<I, O>,
+    restore: Option<C>,
+}
+
+impl<'a, I: io::Read, O: io::Write, C: Command> RestoreGuard<'a, I, O, C> {
+    pub(crate) fn new(terminal: &'a mut Terminal<I, O>, restore: C) -> Self {
+        Self {
+            terminal,
+            restore: Some(restore),
+        }
+    }
+
+    /// Undoes the change now, returning any error from writing the command which undoes it
+    pub fn restore(mut self) -> io::Result<()> {
+        match self.restore.take() {
+            Some(restore) => {
+                self.terminal.queue(restore)?;
+                io::Write::flush(&mut *self.terminal)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I: io::Read, O: io::Write, C: Command> std::ops::Deref for RestoreGuard<'_, I, O, C> {
+    type Target = Terminal<I, O>;
+
+    fn deref(&self) -> &Terminal<I, O> {
+        self.terminal
+    }
+}
+
+impl<I: io::Read, O: io::Write, C: Command> std::ops::DerefMut for RestoreGuard<'_, I, O, C> {
+    fn deref_mut(&mut self) -> &mut Terminal<I, O> {
+        self.terminal
+    }
+}
+
+impl<I: io::Read, O: io::Write, C: Command> Drop for RestoreGuard<'_, I, O, C> {
+    fn drop(&mut self) {
+        if let Some(restore) = self.restore.take() {
+            let _ = self.terminal.queue(restore);
+            let _ = io::Write::flush(&mut *self.terminal);
+        }
+    }
+}
+
+/// Keeps the terminal in raw mode until it is dropped
+///
+/// In raw mode, input is received a byte at a time as it is typed rather than a line at a time,
+/// typed characters are not echoed and keys such as Ctrl-C are received as input rather than
+/// sending signals. Output is not processed either, so `\n` only moves the cursor down and `\r\n`
+/// has to be written to start a new line.
+///
+/// Raw mode applies to the terminal the reader given to [enable](Self::enable) is connected to. If
+/// it is not a terminal, nothing is changed.
+pub struct RawMode {
+    original: Option<sys::OriginalMode>,
+}
+
+impl RawMode {
+    /// Puts the terminal `input` reads from into raw mode
+    ///
+    /// This is usually the reader of a [Terminal], see [Terminal::reader].
+    pub fn enable(input: &impl AsTty) -> io::Result<Self> {
+        input.enable_raw_mode()
+    }
+
+    /// Restores the terminal to the mode it was in before raw mode was enabled
+    pub fn disable(mut self) -> io::Result<()> {
+        match self.original.take() {
+            Some(original) => original.restore(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(original) = self.original.take() {
+            let _ = original.restore();
+        }
+    }
+}
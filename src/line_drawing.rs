@@ -0,0 +1,378 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Box drawing and other symbols from the alternate character set
+//!
+//! Terminals without Unicode, such as serial consoles and the linux console in some locales, can
+//! still draw boxes using their alternate character set, which terminfo describes with the
+//! `smacs`, `rmacs` and `acsc` capabilities. A [LineDrawing] is written with the alternate
+//! character set if the terminal has one which includes it, as a Unicode character otherwise, or
+//! as an ASCII character if the locale isn't UTF-8.
+
+use {
+    crate::{
+        command::{write_expanded, Context},
+        sys,
+        Command,
+        Capability,
+    },
+
+    std::io,
+
+    terminfo::{capability as cap, Database},
+};
+
+/// A symbol from the VT100 alternate character set
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum LineDrawing {
+    Horizontal,
+    Vertical,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// A vertical line with a line going right from its middle
+    LeftTee,
+    /// A vertical line with a line going left from its middle
+    RightTee,
+    /// A horizontal line with a line going down from its middle
+    TopTee,
+    /// A horizontal line with a line going up from its middle
+    BottomTee,
+    Cross,
+    /// Horizontal scan line 1, at the top of the cell
+    ScanLine1,
+    ScanLine3,
+    ScanLine7,
+    /// Horizontal scan line 9, at the bottom of the cell
+    ScanLine9,
+    Block,
+    Board,
+    Checkerboard,
+    Diamond,
+    Lantern,
+    Degree,
+    PlusMinus,
+    Bullet,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    LessEqual,
+    GreaterEqual,
+    NotEqual,
+    Pi,
+    Sterling,
+}
+
+impl LineDrawing {
+    /// The character used for this symbol in `acsc`, which is the character that draws it on a
+    /// VT100
+    pub const fn vt100(self) -> u8 {
+        match self {
+            Self::Horizontal => b'q',
+            Self::Vertical => b'x',
+            Self::TopLeft => b'l',
+            Self::TopRight => b'k',
+            Self::BottomLeft => b'm',
+            Self::BottomRight => b'j',
+            Self::LeftTee => b't',
+            Self::RightTee => b'u',
+            Self::TopTee => b'w',
+            Self::BottomTee => b'v',
+            Self::Cross => b'n',
+            Self::ScanLine1 => b'o',
+            Self::ScanLine3 => b'p',
+            Self::ScanLine7 => b'r',
+            Self::ScanLine9 => b's',
+            Self::Block => b'0',
+            Self::Board => b'h',
+            Self::Checkerboard => b'a',
+            Self::Diamond => b'`',
+            Self::Lantern => b'i',
+            Self::Degree => b'f',
+            Self::PlusMinus => b'g',
+            Self::Bullet => b'~',
+            Self::ArrowLeft => b',',
+            Self::ArrowRight => b'+',
+            Self::ArrowUp => b'-',
+            Self::ArrowDown => b'.',
+            Self::LessEqual => b'y',
+            Self::GreaterEqual => b'z',
+            Self::NotEqual => b'|',
+            Self::Pi => b'{',
+            Self::Sterling => b'}',
+        }
+    }
+
+    pub const fn unicode(self) -> char {
+        match self {
+            Self::Horizontal => '─',
+            Self::Vertical => '│',
+            Self::TopLeft => '┌',
+            Self::TopRight => '┐',
+            Self::BottomLeft => '└',
+            Self::BottomRight => '┘',
+            Self::LeftTee => '├',
+            Self::RightTee => '┤',
+            Self::TopTee => '┬',
+            Self::BottomTee => '┴',
+            Self::Cross => '┼',
+            Self::ScanLine1 => '⎺',
+            Self::ScanLine3 => '⎻',
+            Self::ScanLine7 => '⎼',
+            Self::ScanLine9 => '⎽',
+            Self::Block => '█',
+            Self::Board => '░',
+            Self::Checkerboard => '▒',
+            Self::Diamond => '◆',
+            Self::Lantern => '␋',
+            Self::Degree => '°',
+            Self::PlusMinus => '±',
+            Self::Bullet => '·',
+            Self::ArrowLeft => '←',
+            Self::ArrowRight => '→',
+            Self::ArrowUp => '↑',
+            Self::ArrowDown => '↓',
+            Self::LessEqual => '≤',
+            Self::GreaterEqual => '≥',
+            Self::NotEqual => '≠',
+            Self::Pi => 'π',
+            Self::Sterling => '£',
+        }
+    }
+
+    /// The ASCII character used when neither the alternate character set nor Unicode is available.
+    /// These are the same as the ones ncurses uses.
+    pub const fn ascii(self) -> char {
+        match self {
+            Self::Horizontal | Self::ScanLine1 | Self::ScanLine3 | Self::ScanLine7 => '-',
+            Self::ScanLine9 => '_',
+            Self::Vertical => '|',
+            Self::TopLeft
+            | Self::TopRight
+            | Self::BottomLeft
+            | Self::BottomRight
+            | Self::LeftTee
+            | Self::RightTee
+            | Self::TopTee
+            | Self::BottomTee
+            | Self::Cross
+            | Self::Diamond => '+',
+            Self::Block | Self::Board | Self::Lantern | Self::PlusMinus => '#',
+            Self::Checkerboard => ':',
+            Self::Degree => '\'',
+            Self::Bullet => 'o',
+            Self::ArrowLeft | Self::LessEqual => '<',
+            Self::ArrowRight | Self::GreaterEqual => '>',
+            Self::ArrowUp => '^',
+            Self::ArrowDown => 'v',
+            Self::NotEqual => '!',
+            Self::Pi => '*',
+            Self::Sterling => 'f',
+        }
+    }
+
+    /// Returns the symbol drawn by the Unicode character `c`
+    ///
+    /// The heavy, double and rounded box drawing characters are treated as the light ones, since
+    /// the alternate character set only has one kind of line.
+    pub const fn from_char(c: char) -> Option<Self> {
+        Some(match c {
+            '─' | '━' | '═' => Self::Horizontal,
+            '│' | '┃' | '║' => Self::Vertical,
+            '┌' | '╭' | '┏' | '╔' => Self::TopLeft,
+            '┐' | '╮' | '┓' | '╗' => Self::TopRight,
+            '└' | '╰' | '┗' | '╚' => Self::BottomLeft,
+            '┘' | '╯' | '┛' | '╝' => Self::BottomRight,
+            '├' | '┣' | '╠' => Self::LeftTee,
+            '┤' | '┫' | '╣' => Self::RightTee,
+            '┬' | '┳' | '╦' => Self::TopTee,
+            '┴' | '┻' | '╩' => Self::BottomTee,
+            '┼' | '╋' | '╬' => Self::Cross,
+            '⎺' => Self::ScanLine1,
+            '⎻' => Self::ScanLine3,
+            '⎼' => Self::ScanLine7,
+            '⎽' => Self::ScanLine9,
+            '█' => Self::Block,
+            '░' => Self::Board,
+            '▒' => Self::Checkerboard,
+            '◆' => Self::Diamond,
+            '␋' => Self::Lantern,
+            '°' => Self::Degree,
+            '±' => Self::PlusMinus,
+            '·' => Self::Bullet,
+            '←' => Self::ArrowLeft,
+            '→' => Self::ArrowRight,
+            '↑' => Self::ArrowUp,
+            '↓' => Self::ArrowDown,
+            '≤' => Self::LessEqual,
+            '≥' => Self::GreaterEqual,
+            '≠' => Self::NotEqual,
+            'π' => Self::Pi,
+            '£' => Self::Sterling,
+            _ => return None,
+        })
+    }
+
+    /// Returns a command which writes this symbol `count` times
+    pub const fn repeat(self, count: u16) -> Repeat {
+        Repeat(self, count)
+    }
+}
+
+impl Command for LineDrawing {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write_line_drawings(&[*self], sys::locale_is_utf8(), database, ctx, target)
+    }
+}
+
+impl Capability for LineDrawing {
+    fn is_supported(&self, _: &Database) -> bool {
+        true
+    }
+}
+
+/// Writes a [LineDrawing] a number of times, switching to the alternate character set only once
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Repeat(pub LineDrawing, pub u16);
+
+impl Command for Repeat {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.1 as usize * 3 + 8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let drawings = vec![self.0; self.1 as usize];
+        write_line_drawings(&drawings, sys::locale_is_utf8(), database, ctx, target)
+    }
+}
+
+impl Capability for Repeat {
+    fn is_supported(&self, _: &Database) -> bool {
+        true
+    }
+}
+
+/// Returns the characters this terminal uses for `drawings` in its alternate character set, if
+/// it has one that includes all of them
+fn acs_bytes(drawings: &[LineDrawing], database: &Database) -> Option<Vec<u8>> {
+    let Some(terminfo::Value::String(acsc)) = database.raw("acsc") else {
+        return None;
+    };
+
+    drawings.iter()
+        .map(|drawing| {
+            // acsc is made of pairs of the VT100 character and the character this terminal uses
+            acsc.chunks_exact(2).find(|pair| pair[0] == drawing.vt100()).map(|pair| pair[1])
+        })
+        .collect()
+}
+
+/// Writes `drawings` with the alternate character set if possible, and otherwise as Unicode or, if
+/// `unicode` is false, ASCII characters
+pub(crate) fn write_line_drawings(drawings: &[LineDrawing], unicode: bool, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+    if let Some(acs) = acs_bytes(drawings, database)
+        && let Some(enter) = database.get::<cap::EnterAltCharsetMode>()
+        && let Some(exit) = database.get::<cap::ExitAltCharsetMode>()
+    {
+        write_expanded(database, ctx, target, 1, |expanded, ctx| enter.expand().with(ctx).to(expanded))?;
+        target.write_all(&acs)?;
+        return write_expanded(database, ctx, target, 1, |expanded, ctx| exit.expand().with(ctx).to(expanded));
+    }
+
+    let text: String = match unicode {
+        true => drawings.iter().map(|drawing| drawing.unicode()).collect(),
+        false => drawings.iter().map(|drawing| drawing.ascii()).collect(),
+    };
+
+    target.write_all(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(acsc: Option<&[u8]>) -> Database {
+        let mut database = Database::new();
+        database.name("test")
+            .raw("smacs", b"\x1b(0".as_slice())
+            .raw("rmacs", b"\x1b(B".as_slice());
+
+        if let Some(acsc) = acsc {
+            database.raw("acsc", acsc);
+        }
+
+        database.build().unwrap()
+    }
+
+    fn written(drawings: &[LineDrawing], unicode: bool, database: &Database) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_line_drawings(drawings, unicode, database, &mut Context::new(), &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn alternate_character_set() {
+        // this terminal draws horizontal lines with `R` rather than the VT100's `q`
+        let database = database(Some(b"qRxxlljj"));
+        let drawings = [LineDrawing::TopLeft, LineDrawing::Horizontal, LineDrawing::Horizontal];
+
+        assert_eq!(acs_bytes(&drawings, &database), Some(b"lRR".to_vec()));
+        assert_eq!(written(&drawings, true, &database), b"\x1b(0lRR\x1b(B");
+
+        // a drawing missing from acsc means none of them are drawn with it
+        let drawings = [LineDrawing::TopLeft, LineDrawing::Cross];
+        assert_eq!(acs_bytes(&drawings, &database), None);
+        assert_eq!(written(&drawings, true, &database), "┌┼".as_bytes());
+        assert_eq!(written(&drawings, false, &database), b"++");
+    }
+
+    #[test]
+    fn no_alternate_character_set() {
+        let drawings = [LineDrawing::TopLeft, LineDrawing::Horizontal, LineDrawing::TopRight];
+
+        let database = database(None);
+        assert_eq!(acs_bytes(&drawings, &database), None);
+        assert_eq!(written(&drawings, true, &database), "┌─┐".as_bytes());
+        assert_eq!(written(&drawings, false, &database), b"+-+");
+
+        // acsc is no use without smacs and rmacs
+        let mut database = Database::new();
+        database.name("test").raw("acsc", b"llqqkk".as_slice());
+        let database = database.build().unwrap();
+
+        assert_eq!(written(&drawings, false, &database), b"+-+");
+    }
+
+    #[test]
+    fn odd_length_acsc() {
+        // the last character has no pair, so it isn't taken as a drawing
+        let database = database(Some(b"qqxxl"));
+
+        assert_eq!(acs_bytes(&[LineDrawing::Horizontal, LineDrawing::Vertical], &database), Some(b"qx".to_vec()));
+        assert_eq!(acs_bytes(&[LineDrawing::TopLeft], &database), None);
+        assert_eq!(written(&[LineDrawing::TopLeft], false, &database), b"+");
+    }
+}
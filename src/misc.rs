@@ -16,9 +16,49 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>
 */
 
-use crate::define;
+use {
+    crate::{
+        ansi_capability,
+        base64,
+        command::{is_ansi_terminal, write_expanded, write_extended, Context},
+        define,
+        event::KeyboardEnhancementFlags,
+        Command,
+        Capability,
+    },
 
-use terminfo::capability as cap;
+    std::{io, sync::atomic::{AtomicUsize, Ordering}},
+
+    terminfo::{capability as cap, Database},
+};
+
+/// Defines a command for a user defined capability, which is written as the standard sequence if
+/// the terminfo database doesn't describe it. The command is supported if the database has the
+/// capability or describes an ANSI terminal.
+macro_rules! define_extended {
+    (
+        $(#[$attrs:meta])*
+        definition: pub struct $typ:ident,
+        capability: $capability:literal,
+        fallback: $fallback:literal $(,)?
+    ) => {
+        $(#[$attrs])*
+        #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+        pub struct $typ;
+
+        impl Command for $typ {
+            fn size_hint(&self) -> Option<usize> {
+                Some($fallback.len())
+            }
+
+            fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+                write_extended(database, ctx, target, $capability, &[], $fallback)
+            }
+        }
+
+        ansi_capability!($typ, extended: $capability);
+    };
+}
 
 define!(default-no-args
     definition: pub struct EnterAlternateScreen,
@@ -32,4 +72,578 @@ define!(default-no-args
     capability: cap::ExitCaMode,
     size_hint: Some(20),
     unsupported_msg: "The alternate screen is unsupported on this terminal",
-);
\ No newline at end of file
+);
+define!(default-no-args
+    /// Clears the screen and moves the cursor to the top left corner
+    definition: pub struct ClearScreen,
+    capability: cap::ClearScreen,
+    size_hint: Some(12),
+    unsupported_msg: "Clearing the screen (terminfo cap-name 'clear') is unsupported in this terminal",
+    affected_lines: crate::command::screen_lines,
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Clears from the cursor to the end of the current line
+    definition: pub struct ClearToEndOfLine,
+    capability: cap::ClrEol,
+    size_hint: Some(4),
+    unsupported_msg: "Clearing to the end of the line (terminfo cap-name 'el') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Clears from the cursor to the end of the screen
+    definition: pub struct ClearToEndOfScreen,
+    capability: cap::ClrEos,
+    size_hint: Some(4),
+    unsupported_msg: "Clearing to the end of the screen (terminfo cap-name 'ed') is unsupported in this terminal",
+    affected_lines: crate::command::screen_lines,
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Makes the terminal send the key sequences described in the terminfo database for keys such
+    /// as the arrow keys. This should be enabled before reading [events](crate::event) and disabled
+    /// with [DisableKeypad] afterwards.
+    definition: pub struct EnableKeypad,
+    capability: cap::KeypadXmit,
+    size_hint: Some(8),
+    unsupported_msg: "Keypad transmit mode (terminfo cap-name 'smkx') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define!(default-no-args
+    /// Disables keypad transmit mode (see [EnableKeypad])
+    definition: pub struct DisableKeypad,
+    capability: cap::KeypadLocal,
+    size_hint: Some(8),
+    unsupported_msg: "Keypad transmit mode (terminfo cap-name 'rmkx') is unsupported in this terminal",
+    --add-command-implementation-errors-docs
+);
+
+define_extended! {
+    /// Makes the terminal wrap pasted text in markers so that it is read as a single
+    /// [Paste](crate::Event::Paste) event rather than as key presses. Disable it with
+    /// [DisableBracketedPaste] afterwards.
+    definition: pub struct EnableBracketedPaste,
+    capability: "BE",
+    fallback: b"\x1b[?2004h",
+}
+
+define_extended! {
+    /// Disables bracketed paste mode (see [EnableBracketedPaste])
+    definition: pub struct DisableBracketedPaste,
+    capability: "BD",
+    fallback: b"\x1b[?2004l",
+}
+
+define_extended! {
+    /// Makes the terminal report when its window gains or loses focus, as
+    /// [FocusGained](crate::Event::FocusGained) and [FocusLost](crate::Event::FocusLost) events.
+    /// Disable it with [DisableFocusChange] afterwards.
+    definition: pub struct EnableFocusChange,
+    capability: "fe",
+    fallback: b"\x1b[?1004h",
+}
+
+define_extended! {
+    /// Disables focus reporting (see [EnableFocusChange])
+    definition: pub struct DisableFocusChange,
+    capability: "fd",
+    fallback: b"\x1b[?1004l",
+}
+
+/// Enables kitty keyboard protocol features, saving the ones enabled before so they can be
+/// restored with [PopKeyboardEnhancementFlags]
+///
+/// Terminals which don't support the protocol ignore this and keep sending keys the usual way,
+/// which is still decoded. [QueryKeyboardEnhancementFlags] can be used to find out whether it is
+/// supported.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PushKeyboardEnhancementFlags(pub KeyboardEnhancementFlags);
+
+impl Command for PushKeyboardEnhancementFlags {
+    fn size_hint(&self) -> Option<usize> {
+        Some(7)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write!(target, "\x1b[>{}u", self.0.bits())
+    }
+}
+
+ansi_capability!(PushKeyboardEnhancementFlags);
+
+/// Restores the kitty keyboard protocol features enabled before the last
+/// [PushKeyboardEnhancementFlags]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PopKeyboardEnhancementFlags;
+
+impl Command for PopKeyboardEnhancementFlags {
+    fn size_hint(&self) -> Option<usize> {
+        Some(5)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b[<1u")
+    }
+}
+
+ansi_capability!(PopKeyboardEnhancementFlags);
+
+/// Asks the terminal which kitty keyboard protocol features are enabled
+///
+/// Terminals which support the protocol reply with a
+/// [KeyboardEnhancementFlags](crate::Event::KeyboardEnhancementFlags) event. Other terminals don't
+/// reply at all, so this should be followed by a request every terminal answers, such as primary
+/// device attributes, to avoid waiting forever.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct QueryKeyboardEnhancementFlags;
+
+impl Command for QueryKeyboardEnhancementFlags {
+    fn size_hint(&self) -> Option<usize> {
+        Some(4)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b[?u")
+    }
+}
+
+ansi_capability!(QueryKeyboardEnhancementFlags);
+
+/// Sets the title of the terminal window to `self.0`
+///
+/// If the terminfo database describes a status line (`hs`), the title is written to it with `tsl`
+/// and `fsl`, which is how terminfo describes the window title for terminals such as xterm.
+/// Otherwise the operating system command xterm uses (OSC 2) is written. Control characters are
+/// removed from the title, since they would end it early.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct SetTitle(pub String);
+
+impl Command for SetTitle {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len() + 6)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let title: String = self.0.chars().filter(|c| !c.is_control()).collect();
+
+        if database.get::<cap::HasStatusLine>().is_some_and(|hs| hs.0)
+            && let Some(to_status_line) = database.get::<cap::ToStatusLine>()
+            && let Some(from_status_line) = database.get::<cap::FromStatusLine>()
+        {
+            // the parameter of `tsl` is the column to start at, and parameters are 0 unless set
+            write_expanded(database, ctx, target, 1, |expanded, ctx| to_status_line.expand().with(ctx).to(expanded))?;
+            target.write_all(title.as_bytes())?;
+            return write_expanded(database, ctx, target, 1, |expanded, ctx| from_status_line.expand().with(ctx).to(expanded));
+        }
+
+        write!(target, "\x1b]2;{title}\x1b\\")
+    }
+}
+
+impl Capability for SetTitle {
+    fn is_supported(&self, database: &Database) -> bool {
+        let status_line = database.get::<cap::HasStatusLine>().is_some_and(|hs| hs.0)
+            && database.get::<cap::ToStatusLine>().is_some()
+            && database.get::<cap::FromStatusLine>().is_some();
+
+        status_line || is_ansi_terminal(database)
+    }
+}
+
+/// Sets the name of the terminal window's icon, which some terminals show as the tab's title, to
+/// `self.0`, using the operating system command xterm uses (OSC 1)
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct SetIconName(pub String);
+
+impl Command for SetIconName {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len() + 6)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let name: String = self.0.chars().filter(|c| !c.is_control()).collect();
+        write!(target, "\x1b]1;{name}\x1b\\")
+    }
+}
+
+ansi_capability!(SetIconName);
+
+/// Saves the window title and icon name on the terminal's title stack, so they can be restored with
+/// [PopTitle] after being changed with [SetTitle] or [SetIconName]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PushTitle;
+
+impl Command for PushTitle {
+    fn size_hint(&self) -> Option<usize> {
+        Some(7)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b[22;0t")
+    }
+}
+
+ansi_capability!(PushTitle);
+
+/// Restores the window title and icon name saved by the last [PushTitle]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PopTitle;
+
+impl Command for PopTitle {
+    fn size_hint(&self) -> Option<usize> {
+        Some(7)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b[23;0t")
+    }
+}
+
+ansi_capability!(PopTitle);
+
+/// The ways terminals can be asked to show a desktop notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NotificationProtocol {
+    /// OSC 9, from iTerm2, which only has a message
+    Iterm,
+    /// OSC 777, from rxvt-unicode and used by VTE based terminals
+    Rxvt,
+    /// OSC 99, from kitty
+    Kitty,
+    /// Ringing the bell, which many terminals turn into a notification when they aren't focused
+    Bell,
+}
+
+impl NotificationProtocol {
+    /// Works out which protocol the terminal understands from its name in the terminfo database,
+    /// or from `TN` if it was [probed](crate::Terminal::probe), and the environment variables
+    /// terminals set. This is done when a [Terminal](crate::Terminal) is created or probed, so
+    /// the environment isn't read each time a notification is shown.
+    pub(crate) fn detect_with_env(database: &Database) -> Self {
+        let program = std::env::var("TERM_PROGRAM").ok();
+        Self::detect(database, program.as_deref(), std::env::var_os("VTE_VERSION").is_some())
+    }
+
+    /// Works out which protocol the terminal understands from `database`, `TERM_PROGRAM`
+    /// (`program`) and whether `VTE_VERSION` is set (`vte`)
+    fn detect(database: &Database, program: Option<&str>, vte: bool) -> Self {
+        let mut names: Vec<String> = std::iter::once(database.name())
+            .chain(database.aliases().iter().map(String::as_str))
+            .chain(program)
+            .map(str::to_ascii_lowercase)
+            .collect();
+
+        if let Some(terminfo::Value::String(name)) = database.raw("TN") {
+            names.push(String::from_utf8_lossy(name).to_ascii_lowercase());
+        }
+
+        let named = |wanted: &[&str]| names.iter().any(|name| wanted.iter().any(|wanted| name.contains(wanted)));
+
+        if named(&["kitty"]) {
+            Self::Kitty
+        } else if named(&["iterm", "wezterm", "ghostty", "contour"]) {
+            Self::Iterm
+        } else if named(&["rxvt", "foot"]) || vte {
+            Self::Rxvt
+        } else {
+            Self::Bell
+        }
+    }
+}
+
+/// Shows a desktop notification with `title` and `body`
+///
+/// The terminal is identified from its name and the environment variables it set when the
+/// [Terminal](crate::Terminal) was created, to choose between the notifications of iTerm2 (OSC 9), rxvt-unicode and VTE (OSC 777) and kitty (OSC 99). Other
+/// terminals get the terminfo `bel` capability, which many of them turn into a notification when
+/// they aren't focused. Control characters are removed from the title and body.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Notify {
+    pub title: String,
+    pub body: String,
+}
+
+impl Notify {
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+        }
+    }
+}
+
+impl Command for Notify {
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.title.len() + self.body.len()) * 2 + 40)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let clean = |text: &str| -> String { text.chars().filter(|c| !c.is_control()).collect() };
+        let (title, body) = (clean(&self.title), clean(&self.body));
+
+        let protocol = ctx.notification_protocol().unwrap_or_else(|| NotificationProtocol::detect(database, None, false));
+
+        match protocol {
+            NotificationProtocol::Iterm => match (title.is_empty(), body.is_empty()) {
+                (_, true) => write!(target, "\x1b]9;{title}\x1b\\"),
+                (true, false) => write!(target, "\x1b]9;{body}\x1b\\"),
+                (false, false) => write!(target, "\x1b]9;{title}: {body}\x1b\\"),
+            },
+            // the fields are separated by semicolons, so the title can't have any
+            NotificationProtocol::Rxvt => write!(target, "\x1b]777;notify;{};{body}\x1b\\", title.replace(';', ",")),
+            NotificationProtocol::Kitty => {
+                static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+                let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+                // the title and body are sent as separate chunks of the same notification, which
+                // isn't shown until the chunk with `d=1`. They are base64 encoded (`e=1`) so they
+                // can contain anything.
+                write!(target, "\x1b]99;i={id}:d=0:e=1;{}\x1b\\", base64::encode(title.as_bytes()))?;
+                write!(target, "\x1b]99;i={id}:d=1:e=1:p=body;{}\x1b\\", base64::encode(body.as_bytes()))
+            }
+            NotificationProtocol::Bell => match database.get::<cap::Bell>() {
+                Some(bell) => write_expanded(database, ctx, target, 1, |expanded, ctx| bell.expand().with(ctx).to(expanded)),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+impl Capability for Notify {
+    fn is_supported(&self, database: &Database) -> bool {
+        match NotificationProtocol::detect(database, None, false) {
+            NotificationProtocol::Bell => database.get::<cap::Bell>().is_some(),
+            _ => is_ansi_terminal(database),
+        }
+    }
+}
+
+/// Makes the terminal hold back what is written until [EndSynchronizedUpdate], so that a frame is
+/// shown all at once rather than partly drawn (mode 2026)
+///
+/// The terminfo `Sync` capability is used if the database has it. Terminals which don't support
+/// this may show it as text, see [Terminal::synchronized](crate::Terminal::synchronized) which
+/// checks first.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct BeginSynchronizedUpdate;
+
+impl Command for BeginSynchronizedUpdate {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        // `Sync` takes 1 to begin and 2 to end
+        write_extended(database, ctx, target, "Sync", &[1.into()], b"\x1b[?2026h")
+    }
+}
+
+ansi_capability!(BeginSynchronizedUpdate, extended: "Sync");
+
+/// Shows everything written since [BeginSynchronizedUpdate]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct EndSynchronizedUpdate;
+
+impl Command for EndSynchronizedUpdate {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write_extended(database, ctx, target, "Sync", &[2.into()], b"\x1b[?2026l")
+    }
+}
+
+ansi_capability!(EndSynchronizedUpdate, extended: "Sync");
+
+/// Parses a reply to a request for the state of the private mode `mode` (DECRQM), such as
+/// `ESC [ ? 2026 ; 2 $ y`, returning true if the terminal recognises the mode and it can be changed
+pub(crate) fn parse_mode_report(reply: &[u8], mode: u16) -> Option<bool> {
+    let body = reply.strip_prefix(b"\x1b[?")?.strip_suffix(b"$y")?;
+    let (reply_mode, state) = std::str::from_utf8(body).ok()?.split_once(';')?;
+
+    if reply_mode.parse() != Ok(mode) {
+        return None;
+    }
+
+    // 0 is not recognised, 1 and 2 are set and reset, 3 and 4 are permanently set and reset
+    Some(matches!(state.parse(), Ok(1..=3)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The cursor movement of a terminal which understands ANSI escape sequences
+    const ANSI_CUP: &[u8] = b"\x1b[%i%p1%d;%p2%dH";
+
+    fn written(command: impl Command, database: &Database) -> Vec<u8> {
+        let mut output = Vec::new();
+        command.write_to(database, &mut Context::new(), &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn focus_change() {
+        let mut database = Database::new();
+        database.name("test")
+            .raw("cup", ANSI_CUP)
+            .raw("fe", b"\x1b[?1004;1h".as_slice())
+            .raw("fd", b"\x1b[?1004;1l".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(EnableFocusChange.is_supported(&database));
+        assert_eq!(written(EnableFocusChange, &database), b"\x1b[?1004;1h");
+        assert_eq!(written(DisableFocusChange, &database), b"\x1b[?1004;1l");
+
+        // terminals without fe and fd are sent xterm's sequences
+        let mut database = Database::new();
+        database.name("test").raw("cup", ANSI_CUP);
+        let database = database.build().unwrap();
+
+        assert!(EnableFocusChange.is_supported(&database));
+        assert!(DisableFocusChange.is_supported(&database));
+        assert_eq!(written(EnableFocusChange, &database), b"\x1b[?1004h");
+        assert_eq!(written(DisableFocusChange, &database), b"\x1b[?1004l");
+
+        // unless they don't understand them
+        let mut database = Database::new();
+        database.name("dumb").raw("gn", ());
+        let database = database.build().unwrap();
+
+        assert!(!EnableFocusChange.is_supported(&database));
+        assert!(!DisableFocusChange.is_supported(&database));
+    }
+
+    fn notification(protocol: NotificationProtocol, notify: Notify) -> String {
+        let mut database = Database::new();
+        database.name("test").raw("cup", ANSI_CUP).raw("bel", b"\x07".as_slice());
+
+        let mut ctx = Context::new();
+        ctx.set_notification_protocol(protocol);
+
+        let mut output = Vec::new();
+        notify.write_to(&database.build().unwrap(), &mut ctx, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn notification_protocols() {
+        let database = |name: &str| {
+            let mut database = Database::new();
+            database.name(name);
+            database.build().unwrap()
+        };
+
+        assert_eq!(NotificationProtocol::detect(&database("xterm-kitty"), None, false), NotificationProtocol::Kitty);
+        assert_eq!(NotificationProtocol::detect(&database("xterm-256color"), Some("iTerm.app"), false), NotificationProtocol::Iterm);
+        assert_eq!(NotificationProtocol::detect(&database("rxvt-unicode-256color"), None, false), NotificationProtocol::Rxvt);
+        assert_eq!(NotificationProtocol::detect(&database("xterm-256color"), None, true), NotificationProtocol::Rxvt);
+        assert_eq!(NotificationProtocol::detect(&database("xterm-256color"), None, false), NotificationProtocol::Bell);
+    }
+
+    #[test]
+    fn iterm_notifications() {
+        assert_eq!(notification(NotificationProtocol::Iterm, Notify::new("Build", "done")), "\x1b]9;Build: done\x1b\\");
+        assert_eq!(notification(NotificationProtocol::Iterm, Notify::new("Build", "")), "\x1b]9;Build\x1b\\");
+        assert_eq!(notification(NotificationProtocol::Iterm, Notify::new("", "done")), "\x1b]9;done\x1b\\");
+
+        // control characters would end the sequence early
+        assert_eq!(notification(NotificationProtocol::Iterm, Notify::new("a\x1b\\b", "c\x07d")), "\x1b]9;a\\b: cd\x1b\\");
+    }
+
+    #[test]
+    fn rxvt_notifications() {
+        assert_eq!(notification(NotificationProtocol::Rxvt, Notify::new("Build", "done")), "\x1b]777;notify;Build;done\x1b\\");
+        assert_eq!(notification(NotificationProtocol::Rxvt, Notify::new("a;b", "c;d")), "\x1b]777;notify;a,b;c;d\x1b\\");
+    }
+
+    #[test]
+    fn kitty_notifications() {
+        let output = notification(NotificationProtocol::Kitty, Notify::new("Build", "done"));
+
+        let (title, body) = output.split_once("\x1b\\").unwrap();
+        let id = title.strip_prefix("\x1b]99;i=").unwrap().split(':').next().unwrap();
+
+        assert_eq!(title, format!("\x1b]99;i={id}:d=0:e=1;{}", crate::base64::encode(b"Build")));
+        assert_eq!(body, format!("\x1b]99;i={id}:d=1:e=1:p=body;{}\x1b\\", crate::base64::encode(b"done")));
+
+        // every notification has its own id
+        let next = notification(NotificationProtocol::Kitty, Notify::new("Build", "done"));
+        assert!(!next.starts_with(&format!("\x1b]99;i={id}:")));
+    }
+
+    #[test]
+    fn bell_notifications() {
+        assert_eq!(notification(NotificationProtocol::Bell, Notify::new("Build", "done")), "\x07");
+    }
+
+    #[test]
+    fn mode_reports() {
+        // 0 is an unrecognised mode, 1 and 2 are set and reset, 3 is permanently set and 4 is
+        // permanently reset
+        assert_eq!(parse_mode_report(b"\x1b[?2026;0$y", 2026), Some(false));
+        assert_eq!(parse_mode_report(b"\x1b[?2026;1$y", 2026), Some(true));
+        assert_eq!(parse_mode_report(b"\x1b[?2026;2$y", 2026), Some(true));
+        assert_eq!(parse_mode_report(b"\x1b[?2026;3$y", 2026), Some(true));
+        assert_eq!(parse_mode_report(b"\x1b[?2026;4$y", 2026), Some(false));
+    }
+
+    #[test]
+    fn malformed_mode_reports() {
+        for reply in [
+            // a reply about another mode
+            b"\x1b[?2004;2$y".as_slice(),
+            // without its terminator
+            b"\x1b[?2026;2$",
+            b"\x1b[?2026;2",
+            // an ANSI mode rather than a private one
+            b"\x1b[2026;2$y",
+            b"\x1b[?2026$y",
+            b"\x1b[?x;2$y",
+        ] {
+            assert_eq!(parse_mode_report(reply, 2026), None, "{reply:?}");
+        }
+    }
+
+    #[test]
+    fn titles() {
+        let mut database = Database::new();
+        database.name("test").raw("cup", ANSI_CUP);
+        let database = database.build().unwrap();
+
+        assert!(SetTitle(String::new()).is_supported(&database));
+        assert_eq!(written(SetTitle("a\x1b]2;b\x07c\n".to_owned()), &database), b"\x1b]2;a]2;bc\x1b\\");
+        assert_eq!(written(SetIconName("a\x1bb".to_owned()), &database), b"\x1b]1;ab\x1b\\");
+        assert_eq!(written(PushTitle, &database), b"\x1b[22;0t");
+        assert_eq!(written(PopTitle, &database), b"\x1b[23;0t");
+    }
+
+    #[test]
+    fn titles_on_status_lines() {
+        let mut database = Database::new();
+        database.name("test")
+            .raw("hs", ())
+            .raw("tsl", b"\x1b]0;".as_slice())
+            .raw("fsl", b"\x07".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(SetTitle(String::new()).is_supported(&database));
+        assert_eq!(written(SetTitle("a\x07b".to_owned()), &database), b"\x1b]0;ab\x07");
+
+        // tsl and fsl aren't used without hs, and the terminal doesn't understand OSC 2
+        let mut database = Database::new();
+        database.name("test")
+            .raw("tsl", b"\x1b]0;".as_slice())
+            .raw("fsl", b"\x07".as_slice());
+        let database = database.build().unwrap();
+
+        assert!(!SetTitle(String::new()).is_supported(&database));
+        assert!(!PushTitle.is_supported(&database));
+        assert!(!PopTitle.is_supported(&database));
+    }
+}
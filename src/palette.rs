@@ -0,0 +1,275 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Reading and changing the colors the terminal shows for [Color::ColorById]
+//!
+//! Color schemes usually change at least the first 16 colors of the palette, so the color a
+//! terminal really shows for an id can be quite different from xterm's. Reading the palette with
+//! [Terminal::palette](crate::Terminal::palette) gives the real colors, so that [Color::Rgb] colors can be matched to the
+//! closest one the terminal shows.
+
+use {
+    crate::{
+        ansi_capability,
+        command::{is_ansi_terminal, write_expanded, write_extended, Context},
+        style::Color,
+        Command,
+        Capability,
+        RestoreGuard,
+    },
+
+    std::{io, ops},
+
+    terminfo::{capability as cap, Database},
+};
+
+/// The red, green and blue components of each of the 256 colors with an id
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Palette(pub [(u8, u8, u8); 256]);
+
+impl Palette {
+    /// The colors xterm uses by default. Most terminals use the same colors for ids 16 to 255.
+    pub const XTERM: Self = {
+        const STANDARD: [(u8, u8, u8); 16] = [
+            (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+            (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+            (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+            (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+        ];
+
+        // the levels of each component in the 6x6x6 color cube
+        const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+        let mut colors = [(0, 0, 0); 256];
+        let mut id = 0;
+
+        while id < 256 {
+            colors[id] = match id {
+                0..=15 => STANDARD[id],
+                16..=231 => {
+                    let cube = id - 16;
+                    (LEVELS[cube / 36], LEVELS[cube / 6 % 6], LEVELS[cube % 6])
+                }
+                _ => {
+                    let gray = 8 + (id as u8 - 232) * 10;
+                    (gray, gray, gray)
+                }
+            };
+
+            id += 1;
+        }
+
+        Self(colors)
+    };
+
+    /// Returns the red, green and blue components of the color with the id `id`
+    pub const fn get(&self, id: u8) -> (u8, u8, u8) {
+        self.0[id as usize]
+    }
+
+    pub const fn set(&mut self, id: u8, rgb: (u8, u8, u8)) {
+        self.0[id as usize] = rgb;
+    }
+
+    /// Returns the red, green and blue components of `color`, looking colors described by an id up
+    /// in this palette
+    pub fn rgb(&self, color: Color) -> (u8, u8, u8) {
+        match color {
+            Color::Rgb { r, g, b } => (r, g, b),
+            _ => self.get(color.id(256, self)),
+        }
+    }
+
+    /// Returns the color out of the first `colors` colors of this palette which is closest to
+    /// `color`
+    pub fn closest(&self, color: Color, colors: u16) -> Color {
+        let last = colors.clamp(1, 256) - 1;
+        Color::ColorById(self.closest_id(self.rgb(color), 0..=last as u8))
+    }
+
+    /// Returns the id out of `ids` of the color closest to `rgb`
+    pub(crate) fn closest_id(&self, (r, g, b): (u8, u8, u8), ids: ops::RangeInclusive<u8>) -> u8 {
+        let start = *ids.start();
+
+        let distance = |id: &u8| {
+            let (r2, g2, b2) = self.get(*id);
+            [(r, r2), (g, g2), (b, b2)].into_iter()
+                .map(|(a, b)| (a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        };
+
+        ids.min_by_key(distance).unwrap_or(start)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::XTERM
+    }
+}
+
+/// Asks the terminal for the color it shows for the id `self.0`, which it replies to with an
+/// operating system command (OSC 4). See [Terminal::palette_colors](crate::Terminal::palette_colors).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RequestPaletteColor(pub u8);
+
+impl Command for RequestPaletteColor {
+    fn size_hint(&self) -> Option<usize> {
+        Some(12)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write!(target, "\x1b]4;{};?\x1b\\", self.0)
+    }
+}
+
+ansi_capability!(RequestPaletteColor);
+
+/// Parses a reply to [RequestPaletteColor], such as `ESC ] 4 ; 1 ; rgb:cdcd/0000/0000 ESC \`,
+/// into the id and the color
+pub(crate) fn parse_palette_report(reply: &[u8]) -> Option<(u8, Color)> {
+    let body = reply.strip_prefix(b"\x1b]4;")?;
+    let body = body.strip_suffix(b"\x1b\\").or_else(|| body.strip_suffix(b"\x07"))?;
+
+    let (id, spec) = std::str::from_utf8(body).ok()?.split_once(';')?;
+
+    Some((id.parse().ok()?, crate::style::parse_color_spec(spec)?))
+}
+
+/// Changes the color the terminal shows for the id `self.0` to `self.1`
+///
+/// The terminfo `initc` capability is used if the terminal can change its colors (`ccc`),
+/// otherwise the operating system command xterm uses (OSC 4) is written. The palette can be put
+/// back with [ResetPalette].
+///
+/// A color described by an id is looked up in the [Context::palette] the command is written with,
+/// so it is given the color the terminal shows for that id if the palette has been read.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct SetPaletteColor(pub u8, pub Color);
+
+impl Command for SetPaletteColor {
+    fn size_hint(&self) -> Option<usize> {
+        Some(32)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        // colors with an id are the ones the terminal shows for them
+        let (r, g, b) = ctx.palette().rgb(self.1);
+        let fallback = format!("\x1b]4;{};rgb:{r:02x}/{g:02x}/{b:02x}\x1b\\", self.0);
+
+        if !database.get::<cap::CanChange>().is_some_and(|ccc| ccc.0) {
+            return target.write_all(fallback.as_bytes());
+        }
+
+        // initc takes components from 0 to 1000
+        let scale = |component: u8| (component as i32 * 1000 / 255).into();
+
+        write_extended(database, ctx, target, "initc", &[self.0.into(), scale(r), scale(g), scale(b)], fallback.as_bytes())
+    }
+}
+
+impl Capability for SetPaletteColor {
+    fn is_supported(&self, database: &Database) -> bool {
+        let initc = database.get::<cap::CanChange>().is_some_and(|ccc| ccc.0) && database.raw("initc").is_some();
+        initc || is_ansi_terminal(database)
+    }
+}
+
+/// Puts back the colors the terminal showed before any [SetPaletteColor], using the terminfo `oc`
+/// capability, or the operating system command xterm uses (OSC 104) if the terminal doesn't have
+/// it
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct ResetPalette;
+
+impl Command for ResetPalette {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        match database.get::<cap::OrigColors>() {
+            Some(reset) => write_expanded(database, ctx, target, 1, |expanded, ctx| reset.expand().with(ctx).to(expanded)),
+            None => target.write_all(b"\x1b]104\x1b\\"),
+        }
+    }
+}
+
+impl Capability for ResetPalette {
+    fn is_supported(&self, database: &Database) -> bool {
+        database.get::<cap::OrigColors>().is_some() || is_ansi_terminal(database)
+    }
+}
+
+/// Puts back the terminal's palette when it is dropped, see [Terminal::set_palette](crate::Terminal::set_palette)
+pub type PaletteGuard<'a, I, O> = RestoreGuard<'a, I, O, ResetPalette>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_reports() {
+        let red = Color::Rgb { r: 205, g: 0, b: 0 };
+
+        assert_eq!(parse_palette_report(b"\x1b]4;1;rgb:cdcd/0000/0000\x1b\\"), Some((1, red)));
+        assert_eq!(parse_palette_report(b"\x1b]4;1;rgb:cd/00/00\x07"), Some((1, red)));
+        assert_eq!(parse_palette_report(b"\x1b]4;255;rgb:e/e/e\x07"), Some((255, Color::Rgb { r: 238, g: 238, b: 238 })));
+    }
+
+    #[test]
+    fn rgb_colors_are_matched_against_the_given_palette() {
+        let color = Color::Rgb { r: 1, g: 2, b: 3 };
+        let mut palette = Palette::XTERM;
+        palette.set(100, (1, 2, 3));
+
+        assert_eq!(color.id(256, &Palette::XTERM), 16);
+        assert_eq!(color.id(256, &palette), 100);
+    }
+
+    #[test]
+    fn palette_colors_are_set_from_the_context_palette() {
+        let mut database = Database::new();
+        database.name("test").raw("cup", b"\x1b[%i%p1%d;%p2%dH".as_slice());
+        let database = database.build().unwrap();
+
+        let mut palette = Palette::XTERM;
+        palette.set(1, (0x12, 0x34, 0x56));
+
+        let mut ctx = Context::new();
+        ctx.use_palette(palette);
+
+        let mut output = Vec::new();
+        SetPaletteColor(9, Color::Red).write_to(&database, &mut ctx, &mut output).unwrap();
+        assert_eq!(output, b"\x1b]4;9;rgb:12/34/56\x1b\\");
+    }
+
+    #[test]
+    fn malformed_palette_reports() {
+        for reply in [
+            b"\x1b]4;1;rgb:cdcd/0000/0000".as_slice(),
+            b"\x1b]4;1;rgb:cdcd/0000/0000\x1b",
+            b"\x1b]4;256;rgb:cdcd/0000/0000\x07",
+            b"\x1b]4;x;rgb:cdcd/0000/0000\x07",
+            b"\x1b]4;1;rgb:cdcd/00zz/0000\x07",
+            b"\x1b]4;1\x07",
+            b"\x1b]10;rgb:cdcd/0000/0000\x07",
+        ] {
+            assert_eq!(parse_palette_report(reply), None, "{reply:?}");
+        }
+    }
+}
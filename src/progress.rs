@@ -0,0 +1,749 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Progress bars and spinners
+//!
+//! A [ProgressBar] can be cloned and updated from any thread, while drawing is done by whichever
+//! thread owns the [Terminal], either by calling [ProgressBar::draw] or by adding several bars to a
+//! [MultiProgress] and drawing them all at once.
+//!
+//! When the [Terminal]'s writer is not a terminal, bars are not drawn. Instead, a plain line
+//! describing each bar is written every few seconds (see [MultiProgress::log_interval]).
+//!
+//! ```no_run
+//! use supaterm::{progress::{MultiProgress, ProgressBar}, Terminal};
+//! use std::{io, thread};
+//!
+//! let mut term = Terminal::new(io::stdin().lock(), io::stdout().lock())?;
+//! let mut progress = MultiProgress::new();
+//!
+//! for name in ["first", "second"] {
+//!     let bar = progress.add(ProgressBar::new(100).with_prefix(name));
+//!     thread::spawn(move || {
+//!         for _ in 0..100 {
+//!             bar.inc(1);
+//!         }
+//!         bar.finish();
+//!     });
+//! }
+//!
+//! progress.join(&mut term)?;
+//! # Ok::<(), io::Error>(())
+//! ```
+
+use {
+    crate::{
+        cursor::{MoveToLineStart, MoveUp},
+        misc::{ClearToEndOfLine, ClearToEndOfScreen},
+        style::ContentStyle,
+        text::{str_width, Line, Span},
+        AsTty,
+        Capability,
+        Terminal,
+    },
+
+    std::{
+        io::{self, Write},
+        sync::{Arc, Mutex, MutexGuard},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// A part of a [Template]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    /// the bar, with a fixed width or filling the space left over
+    Bar(Option<usize>),
+    Spinner,
+    Position,
+    Length,
+    Percent,
+    Elapsed,
+    Eta,
+    Rate,
+    Bytes,
+    TotalBytes,
+    BytesPerSecond,
+    Message,
+    Prefix,
+}
+
+/// Describes how a [ProgressBar] is drawn
+///
+/// A template is text containing placeholders in braces which are replaced when the bar is drawn:
+///
+/// | Placeholder        | Replaced with                                                       |
+/// |--------------------|---------------------------------------------------------------------|
+/// | `{bar}`            | the bar, filling the width left over by everything else             |
+/// | `{bar:N}`          | the bar, `N` columns wide                                           |
+/// | `{spinner}`        | the current frame of the spinner                                    |
+/// | `{pos}`, `{len}`   | the position and the length                                         |
+/// | `{percent}`        | the percentage complete                                             |
+/// | `{elapsed}`        | the time since the bar was created                                  |
+/// | `{eta}`            | the estimated time left                                             |
+/// | `{rate}`           | the average number of steps per second                              |
+/// | `{bytes}`, `{total_bytes}`, `{bytes_per_sec}` | the position, length and rate as sizes in bytes |
+/// | `{msg}`, `{prefix}` | the message and the prefix                                         |
+///
+/// `{{` and `}}` are written as `{` and `}`. Unknown placeholders are written as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+    /// the characters for the filled part, the end of the filled part and the empty part of the bar
+    bar_chars: [char; 3],
+    spinner_frames: Vec<String>,
+    bar_style: ContentStyle,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self::new("{prefix} [{elapsed}] [{bar}] {pos}/{len} ({eta}) {msg}")
+    }
+}
+
+impl Template {
+    /// Spinner frames that only use ASCII characters, which are used by default
+    pub const ASCII_SPINNER: &'static [&'static str] = &["|", "/", "-", "\\"];
+    /// Spinner frames made of braille dots
+    pub const DOTS_SPINNER: &'static [&'static str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+    pub fn new(template: &str) -> Self {
+        Self {
+            pieces: parse_template(template),
+            bar_chars: ['=', '>', ' '],
+            spinner_frames: Self::ASCII_SPINNER.iter().map(|frame| frame.to_string()).collect(),
+            bar_style: ContentStyle::new(),
+        }
+    }
+
+    /// The default template for spinners
+    pub fn spinner() -> Self {
+        Self::new("{spinner} {prefix} [{elapsed}] {msg}")
+    }
+
+    /// Sets the characters used for the filled part, the end of the filled part and the empty part
+    /// of the bar
+    pub fn bar_chars(mut self, filled: char, head: char, empty: char) -> Self {
+        self.bar_chars = [filled, head, empty];
+        self
+    }
+
+    pub fn spinner_frames<T: Into<String>>(mut self, frames: impl IntoIterator<Item = T>) -> Self {
+        self.spinner_frames = frames.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the style of the filled part of the bar and the spinner
+    pub fn bar_style(mut self, style: ContentStyle) -> Self {
+        self.bar_style = style;
+        self
+    }
+
+    /// Draws `state` as a line at most `width` columns wide
+    fn render(&self, state: &BarState, width: usize, now: Instant) -> Line {
+
+        let elapsed = state.elapsed(now);
+
+        let values: Vec<Option<String>> = self.pieces.iter()
+            .map(|piece| match piece {
+                Piece::Bar(_) => None,
+                piece => Some(self.render_piece(piece, state, elapsed)),
+            })
+            .collect();
+
+        let used: usize = values.iter().flatten().map(|value| str_width(value)).sum::<usize>()
+            + self.pieces.iter().map(|piece| match piece {
+                Piece::Bar(Some(width)) => *width,
+                _ => 0,
+            }).sum::<usize>();
+        let flexible_bars = self.pieces.iter().filter(|piece| **piece == Piece::Bar(None)).count();
+        // one column is left free so that the line never wraps
+        let flexible_width = width.saturating_sub(used + 1).checked_div(flexible_bars).unwrap_or(0);
+
+        let mut line = Line::new();
+
+        for (piece, value) in self.pieces.iter().zip(values) {
+            match (piece, value) {
+                (Piece::Bar(bar_width), _) => {
+                    let bar_width = bar_width.unwrap_or(flexible_width);
+                    let (filled, empty) = self.render_bar(state, bar_width);
+                    line.push(Span::new(filled, self.bar_style));
+                    line.push(Span::plain(empty));
+                }
+                (Piece::Spinner, Some(value)) => line.push(Span::new(value, self.bar_style)),
+                (_, Some(value)) => line.push(Span::plain(value)),
+                (_, None) => (),
+            }
+        }
+
+        line.truncate(width.saturating_sub(1));
+        line
+    }
+
+    fn render_piece(&self, piece: &Piece, state: &BarState, elapsed: Duration) -> String {
+        let seconds = elapsed.as_secs_f64();
+        let rate = match seconds > 0.0 {
+            true => state.position as f64 / seconds,
+            false => 0.0,
+        };
+
+        match piece {
+            Piece::Text(text) => text.clone(),
+            Piece::Bar(_) => String::new(),
+            Piece::Spinner => match state.finished {
+                Some(_) => String::from(" "),
+                None => {
+                    let frame = (elapsed.as_millis() / 100) as usize;
+                    self.spinner_frames.get(frame % self.spinner_frames.len().max(1)).cloned().unwrap_or_default()
+                }
+            },
+            Piece::Position => state.position.to_string(),
+            Piece::Length => state.length.map_or(String::from("?"), |length| length.to_string()),
+            Piece::Percent => match state.fraction() {
+                Some(fraction) => format!("{:.0}%", fraction * 100.0),
+                None => String::from("?%"),
+            },
+            Piece::Elapsed => format_duration(elapsed),
+            Piece::Eta => match (state.length, state.position) {
+                // an estimate too large for a `Duration` isn't worth showing
+                (Some(length), position) if position > 0 && rate > 0.0 => {
+                    Duration::try_from_secs_f64(length.saturating_sub(position) as f64 / rate)
+                        .map_or(String::from("--:--"), format_duration)
+                }
+                _ => String::from("--:--"),
+            },
+            Piece::Rate => format!("{rate:.1}/s"),
+            Piece::Bytes => format_bytes(state.position as f64),
+            Piece::TotalBytes => state.length.map_or(String::from("?"), |length| format_bytes(length as f64)),
+            Piece::BytesPerSecond => format!("{}/s", format_bytes(rate)),
+            Piece::Message => state.message.replace('\n', " "),
+            Piece::Prefix => state.prefix.clone(),
+        }
+    }
+
+    /// Returns the filled and the empty part of a bar `width` columns wide
+    fn render_bar(&self, state: &BarState, width: usize) -> (String, String) {
+        let [filled_char, head_char, empty_char] = self.bar_chars;
+
+        let filled = (state.fraction().unwrap_or(0.0) * width as f64) as usize;
+        let mut filled_part: String = std::iter::repeat_n(filled_char, filled).collect();
+
+        // the head is only drawn while the bar isn't full
+        let head = usize::from(filled < width && filled > 0);
+        if head == 1 {
+            filled_part.pop();
+            filled_part.push(head_char);
+        }
+
+        (filled_part, std::iter::repeat_n(empty_char, width - filled).collect())
+    }
+}
+
+fn parse_template(template: &str) -> Vec<Piece> {
+
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("{{").or_else(|| rest.strip_prefix("}}")) {
+            text.push(c);
+            rest = after;
+            continue;
+        }
+
+        let placeholder = rest.strip_prefix('{')
+            .and_then(|after| after.split_once('}'))
+            .and_then(|(name, after)| Some((placeholder(name)?, after)));
+
+        match placeholder {
+            Some((piece, after)) => {
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(piece);
+                rest = after;
+            }
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+
+    pieces
+}
+
+fn placeholder(name: &str) -> Option<Piece> {
+    Some(match name {
+        "bar" => Piece::Bar(None),
+        "spinner" => Piece::Spinner,
+        "pos" => Piece::Position,
+        "len" => Piece::Length,
+        "percent" => Piece::Percent,
+        "elapsed" => Piece::Elapsed,
+        "eta" => Piece::Eta,
+        "rate" => Piece::Rate,
+        "bytes" => Piece::Bytes,
+        "total_bytes" => Piece::TotalBytes,
+        "bytes_per_sec" => Piece::BytesPerSecond,
+        "msg" => Piece::Message,
+        "prefix" => Piece::Prefix,
+        name => Piece::Bar(Some(name.strip_prefix("bar:")?.parse().ok()?)),
+    })
+}
+
+/// Formats `duration` as `MM:SS`, or `HH:MM:SS` if it is at least an hour
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match seconds / 3600 {
+        0 => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{hours:02}:{:02}:{:02}", seconds / 60 % 60, seconds % 60),
+    }
+}
+
+/// Formats a number of bytes using binary units, such as `1.50 KiB`
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if bytes < 1024.0 {
+        return format!("{bytes:.0} B");
+    }
+
+    let mut value = bytes / 1024.0;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.2} {}", UNITS[unit])
+}
+
+#[derive(Debug)]
+struct BarState {
+    position: u64,
+    length: Option<u64>,
+    message: String,
+    prefix: String,
+    template: Template,
+    started: Instant,
+    finished: Option<Instant>,
+    /// how the bar has been drawn by [ProgressBar::draw]
+    draw: DrawState,
+}
+
+impl BarState {
+    fn fraction(&self) -> Option<f64> {
+        match self.length {
+            Some(0) => Some(1.0),
+            Some(length) => Some((self.position as f64 / length as f64).min(1.0)),
+            None => None,
+        }
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.finished.unwrap_or(now).duration_since(self.started)
+    }
+
+    /// A plain description of the bar used when output is not a terminal
+    fn log_line(&self, now: Instant) -> String {
+        let mut line = String::new();
+
+        if !self.prefix.is_empty() {
+            line.push_str(&self.prefix);
+            line.push_str(": ");
+        }
+
+        match (self.length, self.fraction()) {
+            (Some(length), Some(fraction)) => line.push_str(&format!("{}/{} ({:.0}%)", self.position, length, fraction * 100.0)),
+            _ => line.push_str(&self.position.to_string()),
+        }
+
+        line.push_str(&format!(" [{}]", format_duration(self.elapsed(now))));
+
+        if self.finished.is_some() {
+            line.push_str(" done");
+        }
+
+        if !self.message.is_empty() {
+            line.push(' ');
+            line.push_str(&self.message.replace('\n', " "));
+        }
+
+        line
+    }
+}
+
+/// A progress bar or spinner
+///
+/// Cloning a bar gives another handle to the same bar, so a bar can be updated from several
+/// threads at once.
+#[derive(Debug, Clone)]
+pub struct ProgressBar {
+    state: Arc<Mutex<BarState>>,
+}
+
+impl ProgressBar {
+    /// Creates a bar which is complete once its position reaches `length`
+    pub fn new(length: u64) -> Self {
+        Self::with_length(Some(length), Template::default())
+    }
+
+    /// Creates a spinner, which is a bar without a length
+    pub fn spinner() -> Self {
+        Self::with_length(None, Template::spinner())
+    }
+
+    fn with_length(length: Option<u64>, template: Template) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BarState {
+                position: 0,
+                length,
+                message: String::new(),
+                prefix: String::new(),
+                template,
+                started: Instant::now(),
+                finished: None,
+                draw: DrawState::new(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, BarState> {
+        // the state is always valid, even if a thread panicked while holding the lock
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn with_template(self, template: Template) -> Self {
+        self.state().template = template;
+        self
+    }
+
+    pub fn with_prefix(self, prefix: impl Into<String>) -> Self {
+        self.set_prefix(prefix);
+        self
+    }
+
+    pub fn with_message(self, message: impl Into<String>) -> Self {
+        self.set_message(message);
+        self
+    }
+
+    pub fn set_template(&self, template: Template) {
+        self.state().template = template;
+    }
+
+    pub fn set_prefix(&self, prefix: impl Into<String>) {
+        self.state().prefix = prefix.into();
+    }
+
+    pub fn set_message(&self, message: impl Into<String>) {
+        self.state().message = message.into();
+    }
+
+    pub fn set_length(&self, length: u64) {
+        self.state().length = Some(length);
+    }
+
+    pub fn set_position(&self, position: u64) {
+        self.state().position = position;
+    }
+
+    /// Advances the position by `delta`
+    pub fn inc(&self, delta: u64) {
+        let mut state = self.state();
+        state.position = state.position.saturating_add(delta);
+    }
+
+    pub fn position(&self) -> u64 {
+        self.state().position
+    }
+
+    pub fn length(&self) -> Option<u64> {
+        self.state().length
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.state().elapsed(Instant::now())
+    }
+
+    /// Marks the bar as finished, filling it if it has a length. The elapsed time stops.
+    pub fn finish(&self) {
+        let mut state = self.state();
+
+        if let Some(length) = state.length {
+            state.position = length;
+        }
+        state.finished.get_or_insert_with(Instant::now);
+    }
+
+    pub fn finish_with_message(&self, message: impl Into<String>) {
+        self.set_message(message);
+        self.finish();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state().finished.is_some()
+    }
+
+    /// Draws the bar over the line the cursor is on, or writes a plain line describing it if
+    /// the terminal's writer is not a terminal and enough time has passed since the last one
+    pub fn draw<I: io::Read, O: io::Write + AsTty>(&self, terminal: &mut Terminal<I, O>) -> io::Result<()> {
+        let mut draw = std::mem::replace(&mut self.state().draw, DrawState::new());
+        let result = draw.draw(terminal, std::slice::from_ref(self));
+        self.state().draw = draw;
+        result
+    }
+}
+
+/// How a set of bars has been drawn
+#[derive(Debug)]
+struct DrawState {
+    /// whether the writer is a terminal, which is checked on the first draw
+    tty: Option<bool>,
+    /// the number of rows drawn last time
+    rows: usize,
+    log_interval: Duration,
+    last_log: Option<Instant>,
+    /// which bars have been logged as finished
+    logged_finished: Vec<bool>,
+}
+
+impl DrawState {
+    fn new() -> Self {
+        Self {
+            tty: None,
+            rows: 0,
+            log_interval: Duration::from_secs(5),
+            last_log: None,
+            logged_finished: Vec::new(),
+        }
+    }
+
+    fn draw<I: io::Read, O: io::Write + AsTty>(&mut self, terminal: &mut Terminal<I, O>, bars: &[ProgressBar]) -> io::Result<()> {
+        let now = Instant::now();
+
+        if !*self.tty.get_or_insert_with(|| terminal.writer().is_tty()) {
+            return self.log(terminal, bars, now);
+        }
+
+        let width = terminal.size().map_or(80, |(columns, _)| columns as usize);
+
+        terminal.queue_if_supported(MoveUp(self.rows.saturating_sub(1) as u16)).transpose()?;
+        terminal.queue_if_supported(MoveToLineStart).transpose()?;
+
+        let clear_screen = ClearToEndOfScreen.is_supported(terminal.database());
+        if clear_screen {
+            terminal.queue(ClearToEndOfScreen)?;
+        }
+
+        for (index, bar) in bars.iter().enumerate() {
+            if index > 0 {
+                terminal.write_all(b"\r\n")?;
+            }
+            if !clear_screen {
+                terminal.queue_if_supported(ClearToEndOfLine).transpose()?;
+            }
+
+            let line = {
+                let state = bar.state();
+                state.template.render(&state, width, now)
+            };
+            terminal.queue(line)?;
+        }
+
+        self.rows = bars.len();
+
+        terminal.flush()
+    }
+
+    /// Writes a plain line for every unfinished bar if enough time has passed, and a final line for
+    /// every bar that has finished since the last time
+    fn log<I: io::Read, O: io::Write>(&mut self, terminal: &mut Terminal<I, O>, bars: &[ProgressBar], now: Instant) -> io::Result<()> {
+        let due = self.last_log.is_none_or(|last_log| now.duration_since(last_log) >= self.log_interval);
+
+        self.logged_finished.resize(bars.len(), false);
+
+        for (bar, logged_finished) in bars.iter().zip(&mut self.logged_finished) {
+            let state = bar.state();
+            let finished = state.finished.is_some();
+
+            if !*logged_finished && (due || finished) {
+                writeln!(terminal, "{}", state.log_line(now))?;
+                *logged_finished = finished;
+            }
+        }
+
+        if due {
+            self.last_log = Some(now);
+        }
+
+        terminal.flush()
+    }
+}
+
+/// Several progress bars drawn together, one per line
+#[derive(Debug)]
+pub struct MultiProgress {
+    bars: Vec<ProgressBar>,
+    draw: DrawState,
+    tick: Duration,
+}
+
+impl Default for MultiProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiProgress {
+    pub fn new() -> Self {
+        Self {
+            bars: Vec::new(),
+            draw: DrawState::new(),
+            tick: Duration::from_millis(100),
+        }
+    }
+
+    /// Adds `bar` below the others and returns another handle to it
+    pub fn add(&mut self, bar: ProgressBar) -> ProgressBar {
+        self.bars.push(bar.clone());
+        bar
+    }
+
+    /// Sets how often plain lines are written when the writer is not a terminal. Defaults to
+    /// every 5 seconds.
+    pub fn log_interval(mut self, interval: Duration) -> Self {
+        self.draw.log_interval = interval;
+        self
+    }
+
+    /// Sets how often [join](Self::join) redraws the bars. Defaults to every 100 milliseconds.
+    pub fn tick(mut self, tick: Duration) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    /// Draws every bar over the previous drawing
+    pub fn draw<I: io::Read, O: io::Write + AsTty>(&mut self, terminal: &mut Terminal<I, O>) -> io::Result<()> {
+        self.draw.draw(terminal, &self.bars)
+    }
+
+    /// Redraws the bars regularly until all of them have finished, and then moves the cursor below
+    /// them
+    pub fn join<I: io::Read, O: io::Write + AsTty>(&mut self, terminal: &mut Terminal<I, O>) -> io::Result<()> {
+        loop {
+            let done = self.bars.iter().all(ProgressBar::is_finished);
+
+            self.draw(terminal)?;
+
+            if done {
+                break;
+            }
+
+            thread::sleep(self.tick);
+        }
+
+        if self.draw.tty == Some(true) {
+            terminal.write_all(b"\r\n")?;
+        }
+
+        terminal.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_placeholders() {
+        assert_eq!(parse_template("{prefix} [{bar:10}] {pos}/{len}"), [
+            Piece::Prefix,
+            Piece::Text(String::from(" [")),
+            Piece::Bar(Some(10)),
+            Piece::Text(String::from("] ")),
+            Piece::Position,
+            Piece::Text(String::from("/")),
+            Piece::Length,
+        ]);
+    }
+
+    #[test]
+    fn keeps_escapes_and_unknown_placeholders_as_text() {
+        assert_eq!(parse_template("{{pos}} {unknown} {bar:x} {msg"), [Piece::Text(String::from("{pos} {unknown} {bar:x} {msg"))]);
+        assert_eq!(parse_template("{spinner}日本{eta}"), [Piece::Spinner, Piece::Text(String::from("日本")), Piece::Eta]);
+        assert_eq!(parse_template(""), []);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "00:00");
+        assert_eq!(format_duration(Duration::from_millis(59_999)), "00:59");
+        assert_eq!(format_duration(Duration::from_secs(3599)), "59:59");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "01:00:00");
+        assert_eq!(format_duration(Duration::from_secs(100 * 3600 + 61)), "100:01:01");
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes(0.0), "0 B");
+        assert_eq!(format_bytes(1023.0), "1023 B");
+        assert_eq!(format_bytes(1536.0), "1.50 KiB");
+        assert_eq!(format_bytes(1024.0 * 1024.0), "1.00 MiB");
+        // values past the largest unit stay in that unit
+        assert_eq!(format_bytes(2048.0 * 1024f64.powi(6)), "2048.00 EiB");
+    }
+
+    #[test]
+    fn renders_bars() {
+        let template = Template::new("{bar}").bar_chars('=', '>', '-');
+        let bar = ProgressBar::new(10);
+
+        let render = |position| {
+            bar.set_position(position);
+            template.render_bar(&bar.state(), 10)
+        };
+
+        assert_eq!(render(0), (String::new(), String::from("----------")));
+        assert_eq!(render(4), (String::from("===>"), String::from("------")));
+        assert_eq!(render(10), (String::from("=========="), String::new()));
+        // a position past the length draws a full bar
+        assert_eq!(render(25), (String::from("=========="), String::new()));
+    }
+
+    #[test]
+    fn eta_too_large_for_a_duration() {
+        let bar = ProgressBar::new(u64::MAX);
+        bar.set_position(1);
+
+        let state = bar.state();
+        let eta = state.template.render_piece(&Piece::Eta, &state, Duration::from_secs(1_000_000));
+
+        assert_eq!(eta, "--:--");
+    }
+}
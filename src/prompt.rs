@@ -0,0 +1,749 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Interactive prompts: [Confirm], [Select], [MultiSelect], [Input] and [Password]
+//!
+//! Each prompt is drawn inline below the cursor and is replaced by a one line summary of the answer
+//! once it has been answered. Pressing Esc or Ctrl-C cancels the prompt, which returns
+//! [Error::Cancelled]. The terminal is put back into the state it was in before the prompt in
+//! every case.
+//!
+//! Prompts are drawn with supaterm's own commands, so styles which the terminal doesn't support are
+//! left out and on terminals which can't move the cursor up, each redraw is written below the last
+//! one instead of over it.
+
+use {
+    crate::{
+        cursor::{Hide, MoveToColumn, MoveToLineStart, MoveUp, Show},
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        misc::{ClearToEndOfScreen, DisableKeypad, EnableKeypad},
+        style::{Color, ContentStyle},
+        text::{str_width, Line, Span},
+        AsTty,
+        Capability,
+        RawMode,
+        Terminal,
+    },
+
+    std::{fmt, io::{self, Write}},
+};
+
+/// An error returned by a prompt
+#[derive(Debug)]
+pub enum Error {
+    /// The user cancelled the prompt with Esc or Ctrl-C
+    Cancelled,
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Cancelled => f.write_str("the prompt was cancelled"),
+            Error::Io(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Cancelled => None,
+            Error::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const MARK_STYLE: ContentStyle = ContentStyle::new().foreground(Color::Green).bold();
+const MESSAGE_STYLE: ContentStyle = ContentStyle::new().bold();
+const HINT_STYLE: ContentStyle = ContentStyle::new().dim();
+const ANSWER_STYLE: ContentStyle = ContentStyle::new().foreground(Color::Cyan);
+const ERROR_STYLE: ContentStyle = ContentStyle::new().foreground(Color::Red);
+
+/// The first line of every prompt: `? <message> <hint>`
+fn header(message: &str, hint: &str) -> Line {
+    let mut line = Line::new();
+    line.push(Span::new("? ", MARK_STYLE));
+    line.push(Span::new(message, MESSAGE_STYLE));
+    line.push(Span::plain(" "));
+    line.push(Span::new(hint, HINT_STYLE));
+    line
+}
+
+/// The line a prompt is replaced with once it has been answered
+fn summary(message: &str, answer: &str) -> Line {
+    let mut line = Line::new();
+    line.push(Span::new("? ", MARK_STYLE));
+    line.push(Span::new(message, MESSAGE_STYLE));
+    line.push(Span::plain(" "));
+    line.push(Span::new(answer, ANSWER_STYLE));
+    line
+}
+
+/// The terminal while a prompt is being shown
+///
+/// Raw mode is enabled for as long as this exists, and the terminal is restored when it is dropped
+/// even if the prompt is cancelled or fails.
+struct Session<'t, I: io::Read, O: io::Write> {
+    terminal: &'t mut Terminal<I, O>,
+    raw_mode: Option<RawMode>,
+    /// the row the cursor was left on by the last draw, relative to the first row drawn
+    cursor_row: usize,
+    drawn: bool,
+    /// whether the previous frame can be drawn over
+    can_redraw: bool,
+}
+
+impl<'t, I: io::Read + AsTty, O: io::Write> Session<'t, I, O> {
+    fn start(terminal: &'t mut Terminal<I, O>, hide_cursor: bool) -> io::Result<Self> {
+        let raw_mode = RawMode::enable(terminal.reader())?;
+
+        terminal.queue_if_supported(EnableKeypad).transpose()?;
+        if hide_cursor {
+            terminal.queue_if_supported(Hide).transpose()?;
+        }
+
+        let can_redraw = MoveUp(1).is_supported(terminal.database())
+            && ClearToEndOfScreen.is_supported(terminal.database());
+
+        Ok(Self {
+            terminal,
+            raw_mode: Some(raw_mode),
+            cursor_row: 0,
+            drawn: false,
+            can_redraw,
+        })
+    }
+}
+
+impl<I: io::Read, O: io::Write> Session<'_, I, O> {
+    /// Draws `lines` over the previous frame and moves the cursor to `cursor`, which is a line index
+    /// and a column in that line
+    fn draw(&mut self, lines: Vec<Line>, cursor: Option<(usize, usize)>) -> io::Result<()> {
+
+        let width = self.terminal.size().map_or(80, |(columns, _)| columns as usize).max(1);
+
+        if self.can_redraw {
+            self.terminal.queue(MoveUp(self.cursor_row as u16))?;
+            self.terminal.queue_if_supported(MoveToLineStart).transpose()?;
+            self.terminal.queue(ClearToEndOfScreen)?;
+        } else if self.drawn {
+            self.terminal.write_all(b"\r\n")?;
+        }
+
+        // the number of rows each line takes up once it has wrapped
+        let rows: Vec<usize> = lines.iter().map(|line| line.width().div_ceil(width).max(1)).collect();
+        let end_row = rows.iter().sum::<usize>() - 1;
+
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                self.terminal.write_all(b"\r\n")?;
+            }
+            self.terminal.queue(line)?;
+        }
+
+        self.cursor_row = end_row;
+
+        if let Some((line, column)) = cursor && self.can_redraw {
+            let row = rows[..line].iter().sum::<usize>() + column / width;
+
+            if row > end_row {
+                self.terminal.write_all(b"\r\n")?;
+            }
+
+            self.terminal.queue(MoveUp(end_row.saturating_sub(row) as u16))?;
+            self.terminal.queue_if_supported(MoveToColumn((column % width) as u16)).transpose()?;
+            self.cursor_row = row;
+        }
+
+        self.drawn = true;
+
+        self.terminal.flush()
+    }
+
+    /// Reads the next key press, returning `Err(Error::Cancelled)` for Esc and Ctrl-C
+    fn read_key(&mut self) -> Result<KeyEvent> {
+        loop {
+            match self.terminal.read_event()? {
+                Event::Key(KeyEvent { kind: KeyEventKind::Release, .. }) => (),
+                Event::Key(KeyEvent { code: KeyCode::Esc, .. })
+                | Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, .. }) => {
+                    return Err(Error::Cancelled);
+                }
+                Event::Key(key) => return Ok(key),
+                // other events, such as key releases and pastes, aren't used by prompts
+                _ => (),
+            }
+        }
+    }
+
+    /// Replaces the prompt with `line`, moves to the next line and restores the terminal
+    fn finish(mut self, line: Line) -> io::Result<()> {
+        self.draw(vec![line], None)?;
+        self.terminal.write_all(b"\r\n")?;
+        self.restore()
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        let Some(raw_mode) = self.raw_mode.take() else {
+            return Ok(());
+        };
+
+        self.terminal.queue_if_supported(Show).transpose()?;
+        self.terminal.queue_if_supported(DisableKeypad).transpose()?;
+        self.terminal.flush()?;
+
+        raw_mode.disable()
+    }
+
+    /// Runs a prompt, replacing it with a summary of the answer or a note that it was cancelled
+    fn run<T>(mut self, message: &str, prompt: impl FnOnce(&mut Self) -> Result<(T, String)>) -> Result<T> {
+        match prompt(&mut self) {
+            Ok((value, answer)) => {
+                self.finish(summary(message, &answer))?;
+                Ok(value)
+            }
+            Err(Error::Cancelled) => {
+                self.finish(summary(message, "cancelled"))?;
+                Err(Error::Cancelled)
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl<I: io::Read, O: io::Write> Drop for Session<'_, I, O> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// Asks a yes or no question
+#[derive(Debug, Clone)]
+pub struct Confirm {
+    message: String,
+    default: Option<bool>,
+}
+
+impl Confirm {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            default: None,
+        }
+    }
+
+    /// Sets the answer used when Enter is pressed. Without a default, Enter does nothing.
+    pub fn default(mut self, default: bool) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Shows the prompt and waits for an answer
+    pub fn interact<I: io::Read + AsTty, O: io::Write>(&self, terminal: &mut Terminal<I, O>) -> Result<bool> {
+
+        let hint = match self.default {
+            Some(true) => "(Y/n)",
+            Some(false) => "(y/N)",
+            None => "(y/n)",
+        };
+
+        Session::start(terminal, false)?.run(&self.message, |session| {
+            let mut line = header(&self.message, hint);
+            line.push(Span::plain(" "));
+            let column = line.width();
+
+            session.draw(vec![line], Some((0, column)))?;
+
+            loop {
+                let answer = match session.read_key()?.code {
+                    KeyCode::Char('y' | 'Y') => true,
+                    KeyCode::Char('n' | 'N') => false,
+                    KeyCode::Enter => match self.default {
+                        Some(default) => default,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                return Ok((answer, String::from(if answer { "yes" } else { "no" })));
+            }
+        })
+    }
+}
+
+/// Moves `selected` in response to the navigation keys. Returns false if `key` isn't one of them.
+fn navigate(key: KeyEvent, selected: &mut usize, count: usize) -> bool {
+    if count == 0 {
+        return false;
+    }
+
+    match (key.code, key.modifiers) {
+        (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => *selected = (*selected + count - 1) % count,
+        (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => *selected = (*selected + 1) % count,
+        (KeyCode::Home, _) => *selected = 0,
+        (KeyCode::End, _) => *selected = count - 1,
+        (KeyCode::PageUp, _) => *selected = selected.saturating_sub(10),
+        (KeyCode::PageDown, _) => *selected = (*selected + 10).min(count - 1),
+        _ => return false,
+    }
+
+    true
+}
+
+/// The items of a list that fit in `max_visible` rows, scrolled so `selected` is visible
+fn visible_range(selected: usize, count: usize, max_visible: usize) -> std::ops::Range<usize> {
+    let max_visible = max_visible.max(1);
+    let start = selected.saturating_sub(max_visible - 1).min(count.saturating_sub(max_visible));
+    start..(start + max_visible).min(count)
+}
+
+fn list_item(prefix: &str, item: &str, selected: bool) -> Line {
+    let mut line = Line::new();
+
+    match selected {
+        true => {
+            line.push(Span::new("> ", ANSWER_STYLE));
+            line.push(Span::new(prefix, ANSWER_STYLE));
+            line.push(Span::new(item, ANSWER_STYLE));
+        }
+        false => {
+            line.push(Span::plain("  "));
+            line.push(Span::plain(prefix));
+            line.push(Span::plain(item));
+        }
+    }
+
+    line
+}
+
+/// Asks for one item to be chosen from a list using the arrow keys
+#[derive(Debug, Clone)]
+pub struct Select {
+    message: String,
+    items: Vec<String>,
+    default: usize,
+    max_visible: usize,
+}
+
+impl Select {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            items: Vec::new(),
+            default: 0,
+            max_visible: 10,
+        }
+    }
+
+    pub fn item(mut self, item: impl Into<String>) -> Self {
+        self.items.push(item.into());
+        self
+    }
+
+    pub fn items<T: Into<String>>(mut self, items: impl IntoIterator<Item = T>) -> Self {
+        self.items.extend(items.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the index of the item which is selected at first
+    pub fn default(mut self, default: usize) -> Self {
+        self.default = default;
+        self
+    }
+
+    /// Sets the most items shown at once. The list scrolls to show the others.
+    pub fn max_visible(mut self, max_visible: usize) -> Self {
+        self.max_visible = max_visible;
+        self
+    }
+
+    /// Shows the prompt and returns the index of the chosen item
+    ///
+    /// Returns an error with an `ErrorKind` of `InvalidInput` if there are no items.
+    pub fn interact<I: io::Read + AsTty, O: io::Write>(&self, terminal: &mut Terminal<I, O>) -> Result<usize> {
+
+        if self.items.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "there are no items to select from").into());
+        }
+
+        let mut selected = self.default.min(self.items.len() - 1);
+
+        Session::start(terminal, true)?.run(&self.message, |session| loop {
+            let mut lines = vec![header(&self.message, "(use the arrow keys, enter to choose)")];
+
+            for index in visible_range(selected, self.items.len(), self.max_visible) {
+                lines.push(list_item("", &self.items[index], index == selected));
+            }
+
+            session.draw(lines, None)?;
+
+            let key = session.read_key()?;
+
+            if key.code == KeyCode::Enter {
+                return Ok((selected, self.items[selected].clone()));
+            }
+
+            navigate(key, &mut selected, self.items.len());
+        })
+    }
+}
+
+/// Asks for any number of items to be chosen from a list
+///
+/// Space toggles the selected item and typing filters the list.
+#[derive(Debug, Clone)]
+pub struct MultiSelect {
+    message: String,
+    items: Vec<(String, bool)>,
+    max_visible: usize,
+}
+
+impl MultiSelect {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            items: Vec::new(),
+            max_visible: 10,
+        }
+    }
+
+    pub fn item(self, item: impl Into<String>) -> Self {
+        self.item_checked(item, false)
+    }
+
+    /// Adds an item which is chosen at first if `checked` is true
+    pub fn item_checked(mut self, item: impl Into<String>, checked: bool) -> Self {
+        self.items.push((item.into(), checked));
+        self
+    }
+
+    pub fn items<T: Into<String>>(mut self, items: impl IntoIterator<Item = T>) -> Self {
+        self.items.extend(items.into_iter().map(|item| (item.into(), false)));
+        self
+    }
+
+    /// Sets the most items shown at once. The list scrolls to show the others.
+    pub fn max_visible(mut self, max_visible: usize) -> Self {
+        self.max_visible = max_visible;
+        self
+    }
+
+    /// Returns the indices of the items containing `filter`, ignoring case
+    fn filtered(&self, filter: &str) -> Vec<usize> {
+        let filter = filter.to_lowercase();
+
+        (0..self.items.len())
+            .filter(|index| self.items[*index].0.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    /// Shows the prompt and returns the indices of the chosen items in ascending order
+    pub fn interact<I: io::Read + AsTty, O: io::Write>(&self, terminal: &mut Terminal<I, O>) -> Result<Vec<usize>> {
+
+        let mut checked: Vec<bool> = self.items.iter().map(|(_, checked)| *checked).collect();
+        let mut filter = String::new();
+        // an index into the filtered items
+        let mut selected = 0;
+
+        Session::start(terminal, true)?.run(&self.message, |session| loop {
+            let filtered = self.filtered(&filter);
+
+            selected = selected.min(filtered.len().saturating_sub(1));
+
+            let mut first = header(&self.message, "(space to toggle, type to filter, enter to choose)");
+            if !filter.is_empty() {
+                first.push(Span::plain(" "));
+                first.push(Span::new(filter.as_str(), ANSWER_STYLE));
+            }
+
+            let mut lines = vec![first];
+
+            for position in visible_range(selected, filtered.len(), self.max_visible) {
+                let index = filtered[position];
+                let checkbox = if checked[index] { "[x] " } else { "[ ] " };
+                lines.push(list_item(checkbox, &self.items[index].0, position == selected));
+            }
+
+            if filtered.is_empty() {
+                lines.push(Line::from(Span::new("  no items match", HINT_STYLE)));
+            }
+
+            session.draw(lines, None)?;
+
+            let key = session.read_key()?;
+
+            match (key.code, key.modifiers) {
+                (KeyCode::Enter, _) => {
+                    let chosen: Vec<usize> = (0..self.items.len()).filter(|index| checked[*index]).collect();
+                    let answer = chosen.iter().map(|index| self.items[*index].0.as_str()).collect::<Vec<_>>().join(", ");
+                    return Ok((chosen, answer));
+                }
+                (KeyCode::Char(' '), _) => {
+                    if let Some(index) = filtered.get(selected) {
+                        checked[*index] = !checked[*index];
+                    }
+                }
+                (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => filter.push(c),
+                (KeyCode::Backspace, _) => {
+                    filter.pop();
+                }
+                _ => {
+                    navigate(key, &mut selected, filtered.len());
+                }
+            }
+        })
+    }
+}
+
+/// Checks the text entered into an [Input], returning a message to show if it is invalid
+pub type Validator = Box<dyn Fn(&str) -> std::result::Result<(), String>>;
+
+/// Asks for a line of text
+pub struct Input {
+    message: String,
+    default: Option<String>,
+    validator: Option<Validator>,
+}
+
+impl Input {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            default: None,
+            validator: None,
+        }
+    }
+
+    /// Sets the text used if nothing is entered
+    pub fn default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    /// Sets a function which checks the text when Enter is pressed. If it returns an error, the
+    /// message is shown and the text can be changed.
+    pub fn validate_with(mut self, validator: impl Fn(&str) -> std::result::Result<(), String> + 'static) -> Self {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Shows the prompt and returns the entered text
+    pub fn interact<I: io::Read + AsTty, O: io::Write>(&self, terminal: &mut Terminal<I, O>) -> Result<String> {
+
+        let hint = match &self.default {
+            Some(default) => format!("({default})"),
+            None => String::new(),
+        };
+
+        Session::start(terminal, false)?.run(&self.message, |session| {
+            let answer = read_text(session, header(&self.message, &hint), false, |text| {
+                let text = match (text.is_empty(), &self.default) {
+                    (true, Some(default)) => default.as_str(),
+                    _ => text,
+                };
+
+                match &self.validator {
+                    Some(validator) => validator(text).map(|()| text.to_owned()),
+                    None => Ok(text.to_owned()),
+                }
+            })?;
+
+            Ok((answer.clone(), answer))
+        })
+    }
+}
+
+/// Asks for a password, without showing what is typed
+pub struct Password {
+    message: String,
+    allow_empty: bool,
+}
+
+impl Password {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            allow_empty: false,
+        }
+    }
+
+    /// Allows an empty password to be entered. Empty passwords are rejected by default.
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.allow_empty = allow_empty;
+        self
+    }
+
+    /// Shows the prompt and returns the entered password
+    pub fn interact<I: io::Read + AsTty, O: io::Write>(&self, terminal: &mut Terminal<I, O>) -> Result<String> {
+        Session::start(terminal, false)?.run(&self.message, |session| {
+            let password = read_text(session, header(&self.message, ""), true, |text| {
+                match text.is_empty() && !self.allow_empty {
+                    true => Err(String::from("the password can't be empty")),
+                    false => Ok(text.to_owned()),
+                }
+            })?;
+
+            Ok((password, String::from("********")))
+        })
+    }
+}
+
+/// Reads a line of text after `header` until `accept` returns `Ok`. If `hidden` is true, the text
+/// isn't shown.
+fn read_text<I: io::Read + AsTty, O: io::Write>(
+    session: &mut Session<I, O>,
+    header: Line,
+    hidden: bool,
+    accept: impl Fn(&str) -> std::result::Result<String, String>,
+) -> Result<String> {
+
+    let mut text = String::new();
+    // a byte index into `text`
+    let mut cursor = 0;
+    let mut error: Option<String> = None;
+
+    loop {
+        let mut line = header.clone();
+        let mut column = line.width();
+
+        if !hidden {
+            column += str_width(&text[..cursor]);
+            line.push(Span::new(text.as_str(), ANSWER_STYLE));
+        }
+
+        let mut lines = vec![line];
+
+        if let Some(error) = &error {
+            lines.push(Line::from(Span::new(error.as_str(), ERROR_STYLE)));
+        }
+
+        session.draw(lines, Some((0, column)))?;
+
+        let key = session.read_key()?;
+
+        // the error is shown until the text is changed or accepted again
+        error = None;
+
+        match (key.code, key.modifiers) {
+            (KeyCode::Enter, _) => match accept(&text) {
+                Ok(accepted) => return Ok(accepted),
+                Err(message) => error = Some(message),
+            },
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                text.insert(cursor, c);
+                cursor += c.len_utf8();
+            }
+            (KeyCode::Backspace, _) | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                if let Some(c) = text[..cursor].chars().next_back() {
+                    cursor -= c.len_utf8();
+                    text.remove(cursor);
+                }
+            }
+            (KeyCode::Delete, _) if cursor < text.len() => {
+                text.remove(cursor);
+            }
+            (KeyCode::Left, _) => cursor -= text[..cursor].chars().next_back().map_or(0, char::len_utf8),
+            (KeyCode::Right, _) => cursor += text[cursor..].chars().next().map_or(0, char::len_utf8),
+            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => cursor = 0,
+            (KeyCode::End, _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => cursor = text.len(),
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                text.drain(..cursor);
+                cursor = 0;
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn scrolling() {
+        // the list only scrolls once the selection reaches the last visible row
+        assert_eq!(visible_range(0, 20, 5), 0..5);
+        assert_eq!(visible_range(4, 20, 5), 0..5);
+        assert_eq!(visible_range(5, 20, 5), 1..6);
+
+        // and stops at the end of the list
+        assert_eq!(visible_range(19, 20, 5), 15..20);
+
+        // short lists are shown whole
+        assert_eq!(visible_range(2, 3, 5), 0..3);
+        assert_eq!(visible_range(0, 0, 5), 0..0);
+        assert_eq!(visible_range(3, 20, 0), 3..4);
+    }
+
+    #[test]
+    fn navigation_wraps_around() {
+        let mut selected = 0;
+
+        assert!(navigate(key(KeyCode::Up), &mut selected, 4));
+        assert_eq!(selected, 3);
+
+        assert!(navigate(key(KeyCode::Down), &mut selected, 4));
+        assert_eq!(selected, 0);
+
+        assert!(navigate(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL), &mut selected, 4));
+        assert_eq!(selected, 3);
+
+        assert!(navigate(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL), &mut selected, 4));
+        assert_eq!(selected, 0);
+
+        // paging stops at the ends rather than wrapping
+        assert!(navigate(key(KeyCode::PageUp), &mut selected, 4));
+        assert_eq!(selected, 0);
+
+        assert!(navigate(key(KeyCode::PageDown), &mut selected, 4));
+        assert_eq!(selected, 3);
+
+        assert!(navigate(key(KeyCode::Home), &mut selected, 4));
+        assert_eq!(selected, 0);
+
+        assert!(navigate(key(KeyCode::End), &mut selected, 4));
+        assert_eq!(selected, 3);
+
+        assert!(!navigate(key(KeyCode::Char('p')), &mut selected, 4));
+        assert!(!navigate(key(KeyCode::Down), &mut selected, 0));
+        assert_eq!(selected, 3);
+    }
+
+    #[test]
+    fn filtering() {
+        let prompt = MultiSelect::new("fruit").items(["Apple", "banana", "Pineapple", "cherry"]);
+
+        assert_eq!(prompt.filtered(""), [0, 1, 2, 3]);
+        assert_eq!(prompt.filtered("apple"), [0, 2]);
+        assert_eq!(prompt.filtered("AN"), [1]);
+        assert_eq!(prompt.filtered("kiwi"), []);
+    }
+}
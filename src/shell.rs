@@ -0,0 +1,187 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Shell integration, which tells the terminal where prompts and the output of commands are and
+//! which directory the shell is in
+//!
+//! Terminals use the semantic marks from FinalTerm (OSC 133) to jump between prompts, select the
+//! output of a command and show whether it failed. A shell writes them in this order:
+//!
+//! 1. [PromptStart] before the prompt
+//! 2. [CommandStart] after the prompt, where the command is typed
+//! 3. [CommandExecuted] once the command has been entered and is about to run
+//! 4. [CommandFinished] once it has exited
+//!
+//! [ReportWorkingDirectory] (OSC 7) lets terminals open new tabs and windows in the same directory.
+
+use {
+    crate::{ansi_capability, command::Context, sys, Command},
+
+    std::{io, path::PathBuf},
+
+    terminfo::Database,
+};
+
+/// Defines a command which writes a FinalTerm semantic mark
+macro_rules! define_mark {
+    (
+        $(#[$attrs:meta])*
+        definition: pub struct $typ:ident,
+        mark: $mark:literal $(,)?
+    ) => {
+        $(#[$attrs])*
+        #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+        pub struct $typ;
+
+        impl Command for $typ {
+            fn size_hint(&self) -> Option<usize> {
+                Some(8)
+            }
+
+            fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+                target.write_all(concat!("\x1b]133;", $mark, "\x1b\\").as_bytes())
+            }
+        }
+
+        ansi_capability!($typ);
+    };
+}
+
+define_mark! {
+    /// Marks the start of a prompt
+    definition: pub struct PromptStart,
+    mark: "A",
+}
+
+define_mark! {
+    /// Marks the end of a prompt and the start of the command typed after it
+    definition: pub struct CommandStart,
+    mark: "B",
+}
+
+define_mark! {
+    /// Marks the end of the command and the start of its output
+    definition: pub struct CommandExecuted,
+    mark: "C",
+}
+
+/// Marks the end of a command's output, with the status it exited with
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct CommandFinished(pub i32);
+
+impl Command for CommandFinished {
+    fn size_hint(&self) -> Option<usize> {
+        Some(12)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        write!(target, "\x1b]133;D;{}\x1b\\", self.0)
+    }
+}
+
+ansi_capability!(CommandFinished);
+
+/// Tells the terminal the shell's working directory is `self.0`, as a `file://` URL with this
+/// machine's host name
+///
+/// The path should be absolute. Bytes which aren't allowed in a URL are percent encoded.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct ReportWorkingDirectory(pub PathBuf);
+
+impl Command for ReportWorkingDirectory {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.as_os_str().len() + 32)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        let host = percent_encode(sys::hostname().as_bytes());
+        let path = self.0.as_os_str().as_encoded_bytes();
+
+        // windows paths such as `C:\Users` are written as `/C:/Users`
+        #[cfg(windows)]
+        let path = &path.iter().map(|byte| if *byte == b'\\' { b'/' } else { *byte }).collect::<Vec<u8>>();
+
+        let path = percent_encode(path);
+        let separator = if path.starts_with('/') { "" } else { "/" };
+
+        write!(target, "\x1b]7;file://{host}{separator}{path}\x1b\\")
+    }
+}
+
+ansi_capability!(ReportWorkingDirectory);
+
+/// Percent encodes the bytes of `bytes` which aren't unreserved characters in a URL, `/` or `:`
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+
+    for byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => encoded.push(*byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(command: impl Command) -> String {
+        let mut database = Database::new();
+        database.name("test");
+
+        let mut output = Vec::new();
+        command.write_to(&database.build().unwrap(), &mut Context::new(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn percent_encoding() {
+        assert_eq!(percent_encode(b"/home/user/My Files"), "/home/user/My%20Files");
+        assert_eq!(percent_encode("/tmp/café".as_bytes()), "/tmp/caf%C3%A9");
+        assert_eq!(percent_encode(b"/tmp/\xff#?%"), "/tmp/%FF%23%3F%25");
+        assert_eq!(percent_encode(b"C:/a-b_c.d~e"), "C:/a-b_c.d~e");
+    }
+
+    #[test]
+    fn working_directory() {
+        let host = percent_encode(sys::hostname().as_bytes());
+
+        assert_eq!(
+            written(ReportWorkingDirectory(PathBuf::from("/home/user/My Files"))),
+            format!("\x1b]7;file://{host}/home/user/My%20Files\x1b\\"),
+        );
+
+        // a relative path still gets a separator after the host name
+        assert_eq!(
+            written(ReportWorkingDirectory(PathBuf::from("src"))),
+            format!("\x1b]7;file://{host}/src\x1b\\"),
+        );
+    }
+
+    #[test]
+    fn semantic_marks() {
+        assert_eq!(written(PromptStart), "\x1b]133;A\x1b\\");
+        assert_eq!(written(CommandStart), "\x1b]133;B\x1b\\");
+        assert_eq!(written(CommandExecuted), "\x1b]133;C\x1b\\");
+        assert_eq!(written(CommandFinished(0)), "\x1b]133;D;0\x1b\\");
+        assert_eq!(written(CommandFinished(-1)), "\x1b]133;D;-1\x1b\\");
+    }
+}
@@ -18,7 +18,10 @@
 
 use {
     crate::{
+        ansi_capability,
+        command::{write_expanded, write_extended, Context},
         define,
+        palette::Palette,
         Command,
         Capability
     },
@@ -48,7 +51,7 @@ impl<'a> Command for Writeln<'a> {
         Some(self.0.len() + 1)
     }
 
-    fn write_to(&self, _database: &Database, _ctx: &mut terminfo::expand::Context, target: &mut dyn io::Write) -> io::Result<()> {
+    fn write_to(&self, _database: &Database, _ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
 
         target.write_all(self.0)?;
 
@@ -75,7 +78,7 @@ impl<'a> Command for Write<'a> {
     fn write_to(
         &self,
         _database: &Database,
-        _ctx: &mut terminfo::expand::Context,
+        _ctx: &mut Context,
         target: &mut dyn io::Write
     ) -> io::Result<()> {
 
@@ -217,6 +220,11 @@ define!(custom-impl
     unsupported_msg: "Setting the foreground color separately to the background color and/or setting any colours is unsupported in this terminal",
     write_to_impl: |self, database, capability, ctx, target| {
 
+        if let Color::Rgb { r, g, b } = self.0 && direct_color(database) {
+            let fallback = format!("\x1b[38;2;{r};{g};{b}m");
+            return write_extended(database, ctx, target, "setrgbf", &[r.into(), g.into(), b.into()], fallback.as_bytes());
+        }
+
         // how many colors are supported and is assumed to be the maximum color value you can have
         let colors = database.get::<cap::MaxColors>()
             .ok_or(io::Error::new(
@@ -224,7 +232,7 @@ define!(custom-impl
                 "No colors! What age is this terminal from?"
             ))?.0;
 
-        let requested_color = self.0.as_u8();
+        let requested_color = self.0.id(colors, ctx.palette());
 
         // if the color requested is supported
         //
@@ -232,15 +240,7 @@ define!(custom-impl
         // if `colors == 8`, the values `0..=7` are the values that can be used in `Expansion::color()`
         if (0..colors).contains(&(requested_color as i32)) {
             // the terminal supports the color
-            match capability.expand().color(requested_color).to(target) {
-                Ok(_) => (),
-                Err(error) => return match error {
-                    terminfo::Error::Io(io_error) => Err(io_error),
-                    terminfo::Error::NotFound => Err(io::Error::new(io::ErrorKind::NotFound, error)),
-                    terminfo::Error::Parse => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
-                    terminfo::Error::Expand(_) => Err(io::Error::new(io::ErrorKind::Other, error)),
-                }
-            }
+            write_expanded(database, ctx, target, 1, |expanded, _| capability.expand().color(requested_color).to(expanded))?;
         } else {
             // the terminal doesn't support the requested color
             return Err(io::Error::new(
@@ -256,7 +256,8 @@ define!(custom-impl
             None => return false,
         };
 
-        let requested_color = self.0.as_u8();
+        // RGB colors are given the id of a color in range with any palette
+        let requested_color = self.0.id(colors, &Palette::XTERM);
 
         (0..colors).contains(&(requested_color as i32))
     }
@@ -269,6 +270,11 @@ define!(custom-impl
     unsupported_msg: "Setting the background color separately to the foreground color and/or setting any colours is unsupported in this terminal",
     write_to_impl: |self, database, capability, ctx, target| {
 
+        if let Color::Rgb { r, g, b } = self.0 && direct_color(database) {
+            let fallback = format!("\x1b[48;2;{r};{g};{b}m");
+            return write_extended(database, ctx, target, "setrgbb", &[r.into(), g.into(), b.into()], fallback.as_bytes());
+        }
+
         // how many colors are supported and is assumed to be the maximum color value you can have
         let colors = database.get::<cap::MaxColors>()
             .ok_or(io::Error::new(
@@ -276,7 +282,7 @@ define!(custom-impl
                 "No colors! What age is this terminal from?"
             ))?.0;
 
-        let requested_color = self.0.as_u8();
+        let requested_color = self.0.id(colors, ctx.palette());
 
         // if the color requested is supported
         //
@@ -284,15 +290,7 @@ define!(custom-impl
         // if `colors == 8`, the values `0..=7` are the values that can be used in `Expansion::color()`
         if (0..colors).contains(&(requested_color as i32)) {
             // the terminal supports the color
-            match capability.expand().color(requested_color).to(target) {
-                Ok(_) => (),
-                Err(error) => return match error {
-                    terminfo::Error::Io(io_error) => Err(io_error),
-                    terminfo::Error::NotFound => Err(io::Error::new(io::ErrorKind::NotFound, error)),
-                    terminfo::Error::Parse => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
-                    terminfo::Error::Expand(_) => Err(io::Error::new(io::ErrorKind::Other, error)),
-                }
-            }
+            write_expanded(database, ctx, target, 1, |expanded, _| capability.expand().color(requested_color).to(expanded))?;
         } else {
             // the terminal doesn't support the requested color
             return Err(io::Error::new(
@@ -311,7 +309,8 @@ define!(custom-impl
             None => return false,
         };
 
-        let requested_color = self.0.as_u8();
+        // RGB colors are given the id of a color in range with any palette
+        let requested_color = self.0.id(colors, &Palette::XTERM);
 
         (0..colors).contains(&(requested_color as i32))
     }
@@ -352,12 +351,20 @@ pub enum Color {
     /// TODO: Add the table to github
     ///
     /// ![testtest](https://github.com/polyagonal1/supaterm/raw/refs/heads/master/images/256-color-mode.png)
-    ColorById(u8)
+    ColorById(u8),
+
+    /// A color described by its red, green and blue components
+    ///
+    /// Terminals without direct color support are given the closest color they do support, going
+    /// by the [Terminal](crate::Terminal)'s palette (see [Terminal::palette](crate::Terminal::palette)).
+    Rgb { r: u8, g: u8, b: u8 },
 }
 
 impl Color {
+    /// Returns the id of this color for a terminal which supports `colors` colors, matching RGB
+    /// colors against `palette`
     #[inline]
-    pub(super) const fn as_u8(&self) -> u8 {
+    pub(super) fn id(&self, colors: i32, palette: &Palette) -> u8 {
         match self {
             Color::Black => 0,
             Color::Red => 1,
@@ -377,7 +384,415 @@ impl Color {
             Color::BrightCyan => 14,
             Color::BrightWhite => 15,
 
-            Color::ColorById(id) => *id
+            Color::ColorById(id) => *id,
+
+            Color::Rgb { r, g, b } => {
+                // the first 16 colors are often changed by color schemes, so they are only used
+                // when there is nothing else
+                let ids = match colors >= 256 {
+                    true => 16..=255,
+                    false => 0..=colors.clamp(1, 16) as u8 - 1,
+                };
+
+                palette.closest_id((*r, *g, *b), ids)
+            }
         }
     }
-}
\ No newline at end of file
+
+    /// Returns the red, green and blue components of this color, using xterm's default palette
+    /// for colors which are described by an id. See [Palette::rgb] for other palettes.
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        Palette::XTERM.rgb(*self)
+    }
+
+    /// Returns how bright this color looks, from 0 for black to 1 for white
+    pub fn luminance(&self) -> f32 {
+        // the relative luminance from sRGB
+        let linear = |component: u8| {
+            let component = component as f32 / 255.0;
+
+            match component <= 0.04045 {
+                true => component / 12.92,
+                false => ((component + 0.055) / 1.055).powf(2.4),
+            }
+        };
+
+        let (r, g, b) = self.to_rgb();
+        0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+    }
+}
+
+/// Returns true if the terminal can be given colors as red, green and blue components
+pub(crate) fn direct_color(database: &Database) -> bool {
+    if ["setrgbf", "RGB", "Tc"].iter().any(|name| database.raw(name).is_some()) {
+        return true;
+    }
+
+    // terminals which support direct color often don't say so in terminfo
+    std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+}
+
+/// Asks the terminal for its default foreground color, which it replies to with an operating system
+/// command (OSC 10). See [Terminal::foreground_color](crate::Terminal::foreground_color).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RequestForegroundColor;
+
+/// Asks the terminal for its default background color, which it replies to with an operating system
+/// command (OSC 11). See [Terminal::background_color](crate::Terminal::background_color).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RequestBackgroundColor;
+
+impl Command for RequestForegroundColor {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b]10;?\x1b\\")
+    }
+}
+
+ansi_capability!(RequestForegroundColor);
+
+impl Command for RequestBackgroundColor {
+    fn size_hint(&self) -> Option<usize> {
+        Some(8)
+    }
+
+    fn write_to(&self, _: &Database, _: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+        target.write_all(b"\x1b]11;?\x1b\\")
+    }
+}
+
+ansi_capability!(RequestBackgroundColor);
+
+/// Parses a reply to [RequestForegroundColor] (`number` 10) or [RequestBackgroundColor]
+/// (`number` 11), such as `ESC ] 11 ; rgb:ffff/ffff/dddd ESC \`
+pub(crate) fn parse_color_report(reply: &[u8], number: u8) -> Option<Color> {
+    let body = reply.strip_prefix(b"\x1b]")?;
+    let body = body.strip_suffix(b"\x1b\\").or_else(|| body.strip_suffix(b"\x07"))?;
+    let body = std::str::from_utf8(body).ok()?;
+
+    let (reply_number, spec) = body.split_once(';')?;
+
+    if reply_number.parse() != Ok(number) {
+        return None;
+    }
+
+    parse_color_spec(spec)
+}
+
+/// Parses an X11 color specification in the form `rgb:r/g/b`, where each component has 1 to 4 hex
+/// digits, or `#rrggbb`
+pub(crate) fn parse_color_spec(spec: &str) -> Option<Color> {
+    if let Some(hex) = spec.strip_prefix('#') {
+        let component = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+
+        if hex.len() != 6 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        return Some(Color::Rgb { r: component(0)?, g: component(2)?, b: component(4)? });
+    }
+
+    // some terminals add an alpha component
+    let components = spec.strip_prefix("rgb:").or_else(|| spec.strip_prefix("rgba:"))?;
+
+    let component = |hex: &str| {
+        if !(1..=4).contains(&hex.len()) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        // scale the component to 8 bits, so `f` and `ffff` are both 255
+        let max = (1u32 << (hex.len() * 4)) - 1;
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        Some((value * 255 / max) as u8)
+    };
+
+    let mut components = components.split('/');
+
+    Some(Color::Rgb {
+        r: component(components.next()?)?,
+        g: component(components.next()?)?,
+        b: component(components.next()?)?,
+    })
+}
+
+/// Whether the terminal has a light or dark background, so colors can be chosen which are
+/// readable on it
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    /// Works out whether `terminal` has a light or dark background
+    ///
+    /// The terminal is asked for its background color (see
+    /// [Terminal::background_color](crate::Terminal::background_color)). If it doesn't reply, the
+    /// `COLORFGBG` environment variable, which some terminals set, is used instead. Returns `None`
+    /// if neither gives an answer.
+    pub fn detect<I: io::Read + crate::PollInput + crate::AsTty, O: io::Write>(terminal: &mut crate::Terminal<I, O>) -> Option<Self> {
+        match terminal.background_color() {
+            Ok(Some(color)) => Some(Self::from_background(color)),
+            _ => Self::from_env(),
+        }
+    }
+
+    /// Classifies a background color as light or dark by its [luminance](Color::luminance)
+    pub fn from_background(color: Color) -> Self {
+        // a luminance of 0.18 looks about halfway between black and white
+        match color.luminance() > 0.18 {
+            true => Self::Light,
+            false => Self::Dark,
+        }
+    }
+
+    /// Works out the theme from the `COLORFGBG` environment variable, which is in the form
+    /// `foreground;background` with both being color ids
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var("COLORFGBG").ok()?;
+
+        // rxvt adds a field for its pixmap in the middle, but the background is always last
+        let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+
+        // the ids of the dark colors in the standard palette
+        match background {
+            0..=6 | 8 => Some(Self::Dark),
+            7 | 9..=15 => Some(Self::Light),
+            _ => None,
+        }
+    }
+}
+
+/// A set of text attributes, such as bold or underlined, which can be combined with `|`
+///
+/// Attributes which are not supported by the terminal are skipped when the style is written
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Attributes(u16);
+
+impl Attributes {
+    pub const BOLD: Self = Self(1 << 0);
+    pub const DIM: Self = Self(1 << 1);
+    pub const UNDERLINE: Self = Self(1 << 2);
+    pub const BLINKING: Self = Self(1 << 3);
+    pub const REVERSE: Self = Self(1 << 4);
+    pub const STANDOUT: Self = Self(1 << 5);
+    pub const INVISIBLE: Self = Self(1 << 6);
+
+    /// No attributes
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every attribute in `other` is also in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for Attributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The style of a piece of content: its colors and attributes
+///
+/// When written as a command, the current style is reset (see [ResetStyle]) and then every part
+/// of this style which the terminal supports is applied. Unsupported parts are skipped rather
+/// than causing an error, so a style degrades gracefully on terminals with few (or no) colors.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct ContentStyle {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub attributes: Attributes,
+}
+
+impl ContentStyle {
+    /// A style with no colors or attributes
+    pub const fn new() -> Self {
+        Self {
+            foreground: None,
+            background: None,
+            attributes: Attributes::empty(),
+        }
+    }
+
+    pub const fn foreground(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    pub const fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub const fn attributes(mut self, attributes: Attributes) -> Self {
+        self.attributes = Attributes(self.attributes.0 | attributes.0);
+        self
+    }
+
+    pub const fn bold(self) -> Self {
+        self.attributes(Attributes::BOLD)
+    }
+
+    pub const fn dim(self) -> Self {
+        self.attributes(Attributes::DIM)
+    }
+
+    pub const fn underline(self) -> Self {
+        self.attributes(Attributes::UNDERLINE)
+    }
+
+    pub const fn reverse(self) -> Self {
+        self.attributes(Attributes::REVERSE)
+    }
+
+    /// Returns true if this style has no colors or attributes
+    pub const fn is_plain(&self) -> bool {
+        self.foreground.is_none() && self.background.is_none() && self.attributes.is_empty()
+    }
+}
+
+/// Writes `cmd` if it is supported, otherwise does nothing
+fn write_if_supported(
+    cmd: impl Command,
+    database: &Database,
+    ctx: &mut Context,
+    target: &mut dyn io::Write,
+) -> io::Result<()> {
+    match cmd.is_supported(database) {
+        true => cmd.write_to(database, ctx, target),
+        false => Ok(()),
+    }
+}
+
+impl Command for ContentStyle {
+    fn size_hint(&self) -> Option<usize> {
+        Some(64)
+    }
+
+    fn write_to(
+        &self,
+        database: &Database,
+        ctx: &mut Context,
+        target: &mut dyn io::Write
+    ) -> io::Result<()> {
+
+        write_if_supported(ResetStyle, database, ctx, target)?;
+
+        let attributes = [
+            (Attributes::BOLD, &SetBold as &dyn Command),
+            (Attributes::DIM, &SetDim),
+            (Attributes::UNDERLINE, &SetUnderline),
+            (Attributes::BLINKING, &SetBlinking),
+            (Attributes::REVERSE, &SetReverseMode),
+            (Attributes::STANDOUT, &SetStandoutMode),
+            (Attributes::INVISIBLE, &SetInvisible),
+        ];
+
+        for (attribute, cmd) in attributes {
+            if self.attributes.contains(attribute) && cmd.is_supported(database) {
+                cmd.write_to(database, ctx, target)?;
+            }
+        }
+
+        if let Some(color) = self.foreground {
+            write_if_supported(SetForegroundColor(color), database, ctx, target)?;
+        }
+
+        if let Some(color) = self.background {
+            write_if_supported(SetBackgroundColor(color), database, ctx, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Capability for ContentStyle {
+    fn is_supported(&self, _: &Database) -> bool {
+        // unsupported parts of the style are skipped
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_specs() {
+        let rgb = |r, g, b| Some(Color::Rgb { r, g, b });
+
+        // each component is scaled from however many hex digits it has
+        assert_eq!(parse_color_spec("rgb:f/0/8"), rgb(255, 0, 136));
+        assert_eq!(parse_color_spec("rgb:ff/00/80"), rgb(255, 0, 128));
+        assert_eq!(parse_color_spec("rgb:fff/000/800"), rgb(255, 0, 127));
+        assert_eq!(parse_color_spec("rgb:ffff/0000/8000"), rgb(255, 0, 127));
+        assert_eq!(parse_color_spec("rgb:ffff/00/8"), rgb(255, 0, 136));
+        assert_eq!(parse_color_spec("rgba:ffff/0000/8000/ffff"), rgb(255, 0, 127));
+        assert_eq!(parse_color_spec("#ff0080"), rgb(255, 0, 128));
+    }
+
+    #[test]
+    fn rgb_colors_use_the_context_palette() {
+        let mut database = Database::new();
+        database.name("test").raw("colors", 256).raw("setaf", b"\x1b[38;5;%p1%dm".as_slice());
+        let database = database.build().unwrap();
+
+        let mut palette = Palette::XTERM;
+        palette.set(100, (1, 2, 3));
+
+        let mut ctx = Context::new();
+        let mut output = Vec::new();
+        let color = SetForegroundColor(Color::Rgb { r: 1, g: 2, b: 3 });
+
+        color.write_to(&database, &mut ctx, &mut output).unwrap();
+        assert_eq!(output, b"\x1b[38;5;16m");
+
+        ctx.use_palette(palette);
+        output.clear();
+        color.write_to(&database, &mut ctx, &mut output).unwrap();
+        assert_eq!(output, b"\x1b[38;5;100m");
+    }
+
+    #[test]
+    fn malformed_color_specs() {
+        for spec in [
+            "", "rgb:", "rgb:ff/ff", "rgb:fffff/0/0", "rgb:/0/0", "rgb:gg/0/0", "rgb:+f/0/0", "hsv:0/0/0",
+            "#ff00", "#ff00800", "#gg0080", "#+f0080",
+        ] {
+            assert_eq!(parse_color_spec(spec), None, "{spec}");
+        }
+    }
+
+    #[test]
+    fn color_reports() {
+        let white = Some(Color::Rgb { r: 255, g: 255, b: 255 });
+
+        assert_eq!(parse_color_report(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\", 11), white);
+        assert_eq!(parse_color_report(b"\x1b]11;rgb:ffff/ffff/ffff\x07", 11), white);
+        assert_eq!(parse_color_report(b"\x1b]10;rgb:ffff/ffff/ffff\x07", 10), white);
+
+        // the reply to the other request
+        assert_eq!(parse_color_report(b"\x1b]10;rgb:ffff/ffff/ffff\x07", 11), None);
+        // not terminated
+        assert_eq!(parse_color_report(b"\x1b]11;rgb:ffff/ffff/ffff", 11), None);
+        assert_eq!(parse_color_report(b"\x1b]11;?\x07", 11), None);
+    }
+}
@@ -0,0 +1,249 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Platform specific functionality which terminfo doesn't cover, such as raw mode and the window
+//! size
+
+use std::io;
+
+#[cfg(unix)]
+mod unix {
+    use std::{io, os::raw::{c_char, c_int, c_short, c_ulong, c_ushort}};
+
+    pub const STDIN: c_int = 0;
+    pub const STDOUT: c_int = 1;
+
+    /// `struct termios` is only ever passed to libc functions, so its layout doesn't need to be
+    /// known. This is large enough to hold it on every platform.
+    #[repr(C, align(8))]
+    #[derive(Clone, Copy)]
+    pub struct Termios([u8; 256]);
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct Winsize {
+        pub rows: c_ushort,
+        pub columns: c_ushort,
+        pub x_pixels: c_ushort,
+        pub y_pixels: c_ushort,
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const TIOCGWINSZ: c_ulong = 0x40087468;
+
+    // TCSANOW is 0 on every unix
+    const TCSANOW: c_int = 0;
+
+    #[repr(C)]
+    pub struct PollFd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+
+    const POLLIN: c_short = 1;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    type NFds = c_ulong;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    type NFds = std::os::raw::c_uint;
+
+    unsafe extern "C" {
+        fn isatty(fd: c_int) -> c_int;
+        fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+        fn tcsetattr(fd: c_int, optional_actions: c_int, termios: *const Termios) -> c_int;
+        fn cfmakeraw(termios: *mut Termios);
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        fn poll(fds: *mut PollFd, nfds: NFds, timeout: c_int) -> c_int;
+        fn gethostname(name: *mut c_char, len: usize) -> c_int;
+    }
+
+    pub fn is_tty(fd: c_int) -> bool {
+        // SAFETY: isatty has no preconditions
+        unsafe { isatty(fd) == 1 }
+    }
+
+    pub fn get_termios(fd: c_int) -> io::Result<Termios> {
+        let mut termios = Termios([0; 256]);
+        // SAFETY: `termios` is large enough to hold a `struct termios`
+        match unsafe { tcgetattr(fd, &mut termios) } {
+            0 => Ok(termios),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    pub fn set_termios(fd: c_int, termios: &Termios) -> io::Result<()> {
+        // SAFETY: `termios` was filled in by `tcgetattr`
+        match unsafe { tcsetattr(fd, TCSANOW, termios) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    pub fn make_raw(termios: &mut Termios) {
+        // SAFETY: `termios` was filled in by `tcgetattr`
+        unsafe { cfmakeraw(termios) }
+    }
+
+    /// Waits up to `timeout` milliseconds for `fd` to have input, returning false if it didn't
+    pub fn poll_input(fd: c_int, timeout: c_int) -> io::Result<bool> {
+        let mut poll_fd = PollFd { fd, events: POLLIN, revents: 0 };
+        // SAFETY: `poll_fd` is a single valid `struct pollfd`
+        match unsafe { poll(&mut poll_fd, 1, timeout) } {
+            -1 => Err(io::Error::last_os_error()),
+            ready => Ok(ready > 0),
+        }
+    }
+
+    pub fn hostname() -> io::Result<Vec<u8>> {
+        // host names are at most 255 bytes, plus the nul terminator
+        let mut name = [0u8; 256];
+        // SAFETY: `name` is valid for `name.len()` bytes
+        match unsafe { gethostname(name.as_mut_ptr().cast(), name.len()) } {
+            0 => Ok(name.split(|byte| *byte == 0).next().unwrap_or_default().to_vec()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    pub fn window_size(fd: c_int) -> io::Result<Winsize> {
+        let mut size = Winsize::default();
+        // SAFETY: TIOCGWINSZ takes a pointer to a `struct winsize`
+        match unsafe { ioctl(fd, TIOCGWINSZ, &mut size as *mut Winsize) } {
+            0 => Ok(size),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+/// Returns true if `fd` is a terminal
+#[cfg(unix)]
+pub fn is_tty(fd: std::os::fd::BorrowedFd) -> bool {
+    use std::os::fd::AsRawFd;
+
+    unix::is_tty(fd.as_raw_fd())
+}
+
+/// Returns the name of this machine, or an empty string if it isn't known
+pub fn hostname() -> String {
+    #[cfg(unix)]
+    return unix::hostname().map(|name| String::from_utf8_lossy(&name).into_owned()).unwrap_or_default();
+    #[cfg(not(unix))]
+    return std::env::var("COMPUTERNAME").unwrap_or_default();
+}
+
+/// Returns true if the locale set in the environment uses UTF-8, which is taken to mean that the
+/// terminal can display Unicode
+pub fn locale_is_utf8() -> bool {
+    #[cfg(unix)]
+    return ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        });
+    // terminals on other platforms are assumed to handle Unicode
+    #[cfg(not(unix))]
+    return true;
+}
+
+/// Waits until `fd` has input to read, returning false if `timeout` passes first
+#[cfg(unix)]
+pub fn wait_for_input(fd: std::os::fd::BorrowedFd, timeout: std::time::Duration) -> io::Result<bool> {
+    use std::{os::fd::AsRawFd, time::Instant};
+
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        // rounded up so that a timeout of less than a millisecond doesn't become 0
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let milliseconds = remaining.as_nanos().div_ceil(1_000_000).min(i32::MAX as u128) as i32;
+
+        match unix::poll_input(fd.as_raw_fd(), milliseconds) {
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+/// The size of the terminal window as reported by the OS
+///
+/// Fields are 0 if the OS doesn't know them (which is common for the pixel sizes)
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct WindowSize {
+    pub columns: u16,
+    pub rows: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+pub fn window_size() -> io::Result<WindowSize> {
+    #[cfg(unix)]
+    {
+        let size = unix::window_size(unix::STDOUT).or_else(|_| unix::window_size(unix::STDIN))?;
+
+        Ok(WindowSize {
+            columns: size.columns,
+            rows: size.rows,
+            width: size.x_pixels,
+            height: size.y_pixels,
+        })
+    }
+    #[cfg(not(unix))]
+    Err(io::Error::new(io::ErrorKind::Unsupported, "querying the window size is unsupported on this platform"))
+}
+
+/// Puts the terminal `fd` is connected to into raw mode and returns the original settings so
+/// they can be restored
+///
+/// Returns `Ok(None)` if `fd` is not a terminal
+#[cfg(unix)]
+pub fn enable_raw_mode(fd: std::os::fd::BorrowedFd) -> io::Result<Option<OriginalMode>> {
+    use std::os::fd::AsRawFd;
+
+    let fd = fd.as_raw_fd();
+
+    if !unix::is_tty(fd) {
+        return Ok(None);
+    }
+
+    let original = unix::get_termios(fd)?;
+    let mut raw = original;
+    unix::make_raw(&mut raw);
+    unix::set_termios(fd, &raw)?;
+
+    Ok(Some(OriginalMode { fd, termios: original }))
+}
+
+/// The terminal settings from before raw mode was enabled, and the file descriptor they belong to
+pub struct OriginalMode {
+    #[cfg(unix)]
+    fd: std::os::raw::c_int,
+    #[cfg(unix)]
+    termios: unix::Termios,
+}
+
+impl OriginalMode {
+    pub fn restore(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        return unix::set_termios(self.fd, &self.termios);
+        #[cfg(not(unix))]
+        return Ok(());
+    }
+}
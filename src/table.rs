@@ -0,0 +1,671 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Tables of styled text
+//!
+//! ```no_run
+//! use supaterm::{table::{Border, Column, Table}, text::Justify, Terminal};
+//! use std::io::{self, Write};
+//!
+//! let mut term = Terminal::new(io::stdin().lock(), io::stdout().lock())?;
+//!
+//! let table = Table::new()
+//!     .header(["Name", "Size"])
+//!     .row(["Cargo.toml", "1.2 KiB"])
+//!     .row(["src", "-"])
+//!     .column(1, Column::new().justify(Justify::Right))
+//!     .border(Border::ROUNDED);
+//!
+//! term.queue(table)?;
+//! term.flush()?;
+//! # Ok::<(), io::Error>(())
+//! ```
+
+use {
+    crate::{
+        command::Context,
+        line_drawing::{write_line_drawings, LineDrawing},
+        style::{ContentStyle, ResetStyle},
+        sys,
+        text::{self, str_width, Justify, Line, Span, WrapOptions},
+        Command,
+        Capability,
+    },
+
+    std::io,
+
+    terminfo::Database,
+};
+
+/// What happens to text which is wider than its column
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum Overflow {
+    /// The text is cut off and ends with an ellipsis
+    #[default]
+    Truncate,
+    /// The text is wrapped onto more lines (see [text::wrap])
+    Wrap,
+}
+
+/// Describes how a column of a [Table] is laid out
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Column {
+    pub justify: Justify,
+    pub overflow: Overflow,
+    /// The column is never made narrower than this, even if the table doesn't fit
+    pub min_width: usize,
+    /// The column is never made wider than this, even if its text doesn't fit
+    pub max_width: Option<usize>,
+}
+
+impl Default for Column {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Column {
+    pub const fn new() -> Self {
+        Self {
+            justify: Justify::Left,
+            overflow: Overflow::Truncate,
+            min_width: 1,
+            max_width: None,
+        }
+    }
+
+    /// Sets how text is positioned in the column. [Justify::Full] only has an effect on wrapped
+    /// text, and is the same as [Justify::Left] otherwise.
+    pub const fn justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub const fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub const fn min_width(mut self, width: usize) -> Self {
+        self.min_width = width;
+        self
+    }
+
+    pub const fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Makes the column exactly `width` columns wide
+    pub const fn fixed_width(self, width: usize) -> Self {
+        self.min_width(width).max_width(width)
+    }
+}
+
+/// The characters a table's border is drawn with
+///
+/// Borders which use box drawing characters are drawn with the terminal's alternate character set
+/// when the terminal can't display Unicode (see [Table::unicode]), or in ASCII if the terminal has
+/// no alternate character set (see [LineDrawing]).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Border {
+    pub horizontal: char,
+    pub vertical: char,
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    /// Where a vertical line meets the top line
+    pub top_tee: char,
+    /// Where a vertical line meets the bottom line
+    pub bottom_tee: char,
+    /// Where the line below the header meets the left line
+    pub left_tee: char,
+    /// Where the line below the header meets the right line
+    pub right_tee: char,
+    /// Where the line below the header crosses a vertical line
+    pub cross: char,
+}
+
+impl Border {
+    pub const ASCII: Self = Self::uniform('-', '|', '+');
+
+    pub const SINGLE: Self = Self {
+        horizontal: '─',
+        vertical: '│',
+        top_left: '┌',
+        top_right: '┐',
+        bottom_left: '└',
+        bottom_right: '┘',
+        top_tee: '┬',
+        bottom_tee: '┴',
+        left_tee: '├',
+        right_tee: '┤',
+        cross: '┼',
+    };
+
+    pub const ROUNDED: Self = Self {
+        top_left: '╭',
+        top_right: '╮',
+        bottom_left: '╰',
+        bottom_right: '╯',
+        ..Self::SINGLE
+    };
+
+    pub const HEAVY: Self = Self {
+        horizontal: '━',
+        vertical: '┃',
+        top_left: '┏',
+        top_right: '┓',
+        bottom_left: '┗',
+        bottom_right: '┛',
+        top_tee: '┳',
+        bottom_tee: '┻',
+        left_tee: '┣',
+        right_tee: '┫',
+        cross: '╋',
+    };
+
+    pub const DOUBLE: Self = Self {
+        horizontal: '═',
+        vertical: '║',
+        top_left: '╔',
+        top_right: '╗',
+        bottom_left: '╚',
+        bottom_right: '╝',
+        top_tee: '╦',
+        bottom_tee: '╩',
+        left_tee: '╠',
+        right_tee: '╣',
+        cross: '╬',
+    };
+
+    /// A border with the same character for every corner, tee and cross
+    pub const fn uniform(horizontal: char, vertical: char, corner: char) -> Self {
+        Self {
+            horizontal,
+            vertical,
+            top_left: corner,
+            top_right: corner,
+            bottom_left: corner,
+            bottom_right: corner,
+            top_tee: corner,
+            bottom_tee: corner,
+            left_tee: corner,
+            right_tee: corner,
+            cross: corner,
+        }
+    }
+}
+
+/// A part of a rendered line of a table
+enum Segment {
+    Border(String),
+    Cell(Line),
+}
+
+/// A table of styled text
+///
+/// When written as a command, every line of the table is followed by a newline, so the cursor ends
+/// up at the start of the line below the table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    header: Option<Vec<Line>>,
+    rows: Vec<Vec<Line>>,
+    columns: Vec<Column>,
+    border: Option<Border>,
+    header_style: ContentStyle,
+    border_style: ContentStyle,
+    max_width: Option<usize>,
+    unicode: Option<bool>,
+}
+
+impl Table {
+    /// Creates an empty table without a border
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn header<T: Into<Line>>(mut self, cells: impl IntoIterator<Item = T>) -> Self {
+        self.header = Some(cells.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn row<T: Into<Line>>(mut self, cells: impl IntoIterator<Item = T>) -> Self {
+        self.push_row(cells);
+        self
+    }
+
+    pub fn push_row<T: Into<Line>>(&mut self, cells: impl IntoIterator<Item = T>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    /// Sets the layout of the column at `index`. Columns default to [Column::new].
+    pub fn column(mut self, index: usize, column: Column) -> Self {
+        if self.columns.len() <= index {
+            self.columns.resize(index + 1, Column::new());
+        }
+
+        self.columns[index] = column;
+        self
+    }
+
+    pub fn border(mut self, border: Border) -> Self {
+        self.border = Some(border);
+        self
+    }
+
+    /// Sets the style used for the header. Parts of the header which have their own style keep it,
+    /// with any attributes from this style added.
+    pub fn header_style(mut self, style: ContentStyle) -> Self {
+        self.header_style = style;
+        self
+    }
+
+    pub fn border_style(mut self, style: ContentStyle) -> Self {
+        self.border_style = style;
+        self
+    }
+
+    /// Limits the width of the whole table, including the border. Columns are narrowed, widest
+    /// first, until the table fits or every column is at its minimum width.
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Sets whether the terminal can display Unicode. By default, this is guessed from the locale
+    /// environment variables.
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = Some(unicode);
+        self
+    }
+
+    fn column_count(&self) -> usize {
+        self.header.iter().chain(&self.rows).map(Vec::len).max().unwrap_or(0)
+    }
+
+    fn layout(&self, index: usize) -> Column {
+        self.columns.get(index).copied().unwrap_or_default()
+    }
+
+    /// Works out the width of the text in each column
+    fn column_widths(&self) -> Vec<usize> {
+        let count = self.column_count();
+
+        let mut widths: Vec<usize> = (0..count)
+            .map(|index| {
+                let layout = self.layout(index);
+                let natural = self.header.iter().chain(&self.rows)
+                    .filter_map(|row| row.get(index))
+                    .map(cell_width)
+                    .max()
+                    .unwrap_or(0);
+
+                natural.min(layout.max_width.unwrap_or(usize::MAX)).max(layout.min_width)
+            })
+            .collect();
+
+        let Some(max_width) = self.max_width else {
+            return widths;
+        };
+
+        let overhead = match self.border {
+            // a vertical line before each column and after the last, and a space either side of
+            // the text
+            Some(_) => 3 * count + 1,
+            // two spaces between columns
+            None => 2 * count.saturating_sub(1),
+        };
+        let available = max_width.saturating_sub(overhead);
+
+        while widths.iter().sum::<usize>() > available {
+            let widest = (0..count)
+                .filter(|&index| widths[index] > self.layout(index).min_width.max(1))
+                .max_by_key(|&index| widths[index]);
+
+            match widest {
+                Some(index) => widths[index] -= 1,
+                None => break,
+            }
+        }
+
+        widths
+    }
+
+    /// Lays a row out into lines of segments
+    fn render_row(&self, row: &[Line], widths: &[usize], style: Option<ContentStyle>, unicode: bool, output: &mut Vec<Vec<Segment>>) {
+
+        let cells: Vec<Vec<Line>> = widths.iter().enumerate()
+            .map(|(index, &width)| {
+                let mut cell = row.get(index).cloned().unwrap_or_default();
+                if let Some(style) = style {
+                    for span in &mut cell.spans {
+                        span.style = with_base(span.style, style);
+                    }
+                }
+                layout_cell(&cell, width, self.layout(index), unicode)
+            })
+            .collect();
+
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+        for line_index in 0..height {
+            let mut line = Vec::new();
+
+            for (index, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+                let text = cell.get(line_index).cloned().unwrap_or_else(|| pad(Line::new(), width, Justify::Left));
+
+                match self.border {
+                    Some(border) => {
+                        let start = match index {
+                            0 => format!("{} ", border.vertical),
+                            _ => format!(" {} ", border.vertical),
+                        };
+                        line.push(Segment::Border(start));
+                    }
+                    None if index > 0 => line.push(Segment::Cell(Line::from("  "))),
+                    None => (),
+                }
+
+                line.push(Segment::Cell(text));
+            }
+
+            if let Some(border) = self.border {
+                line.push(Segment::Border(format!(" {}", border.vertical)));
+            }
+
+            output.push(line);
+        }
+    }
+
+    /// Lays the whole table out into lines of segments
+    fn render(&self, unicode: bool) -> Vec<Vec<Segment>> {
+        let widths = self.column_widths();
+        let mut output = Vec::new();
+
+        if widths.is_empty() {
+            return output;
+        }
+
+        let rule = |left: char, middle: char, right: char, horizontal: char| {
+            let mut rule = String::from(left);
+            for (index, width) in widths.iter().enumerate() {
+                if index > 0 {
+                    rule.push(middle);
+                }
+                rule.extend(std::iter::repeat_n(horizontal, width + 2));
+            }
+            rule.push(right);
+            vec![Segment::Border(rule)]
+        };
+
+        if let Some(border) = self.border {
+            output.push(rule(border.top_left, border.top_tee, border.top_right, border.horizontal));
+        }
+
+        if let Some(header) = &self.header {
+            self.render_row(header, &widths, Some(self.header_style), unicode, &mut output);
+
+            if let Some(border) = self.border {
+                output.push(rule(border.left_tee, border.cross, border.right_tee, border.horizontal));
+            }
+        }
+
+        for row in &self.rows {
+            self.render_row(row, &widths, None, unicode, &mut output);
+        }
+
+        if let Some(border) = self.border {
+            output.push(rule(border.bottom_left, border.bottom_tee, border.bottom_right, border.horizontal));
+        }
+
+        output
+    }
+
+    /// Writes part of the border, using the alternate character set or ASCII if Unicode can't be
+    /// displayed
+    fn write_border(&self, text: &str, unicode: bool, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+        if !self.border_style.is_plain() {
+            self.border_style.write_to(database, ctx, target)?;
+        }
+
+        if unicode || text.is_ascii() {
+            target.write_all(text.as_bytes())?;
+        } else {
+            // the spaces either side of a vertical line are written as they are
+            let trimmed = text.trim_matches(' ');
+            let leading = text.len() - text.trim_start_matches(' ').len();
+            let drawings: Option<Vec<LineDrawing>> = trimmed.chars().map(LineDrawing::from_char).collect();
+
+            match drawings {
+                Some(drawings) => {
+                    target.write_all(&text.as_bytes()[..leading])?;
+                    write_line_drawings(&drawings, false, database, ctx, target)?;
+                    target.write_all(&text.as_bytes()[leading + trimmed.len()..])?;
+                }
+                None => target.write_all(text.as_bytes())?,
+            }
+        }
+
+        if !self.border_style.is_plain() && ResetStyle.is_supported(database) {
+            ResetStyle.write_to(database, ctx, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Command for Table {
+    fn size_hint(&self) -> Option<usize> {
+        let cells: usize = self.header.iter().chain(&self.rows)
+            .flatten()
+            .map(|cell| cell.size_hint().unwrap_or(0) + 16)
+            .sum();
+
+        Some(cells + 256)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+        let unicode = self.unicode.unwrap_or_else(sys::locale_is_utf8);
+
+        for line in self.render(unicode) {
+            for segment in line {
+                match segment {
+                    Segment::Border(text) => self.write_border(&text, unicode, database, ctx, target)?,
+                    Segment::Cell(text) => text.write_to(database, ctx, target)?,
+                }
+            }
+
+            target.write_all(b"\r\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Capability for Table {
+    fn is_supported(&self, _: &Database) -> bool {
+        true
+    }
+}
+
+/// Returns `style` with the colors from `base` that `style` doesn't set and the attributes of both
+fn with_base(style: ContentStyle, base: ContentStyle) -> ContentStyle {
+    ContentStyle {
+        foreground: style.foreground.or(base.foreground),
+        background: style.background.or(base.background),
+        attributes: style.attributes | base.attributes,
+    }
+}
+
+/// The width of the widest line of a cell
+fn cell_width(cell: &Line) -> usize {
+    let text: String = cell.spans.iter().map(|span| span.content.as_str()).collect();
+    text.split('\n').map(str_width).max().unwrap_or(0)
+}
+
+/// Lays a cell out into lines exactly `width` columns wide
+fn layout_cell(cell: &Line, width: usize, column: Column, unicode: bool) -> Vec<Line> {
+
+    let lines: Vec<Line> = match column.overflow {
+        Overflow::Wrap => {
+            // wrapping already positions the text within the width
+            let options = WrapOptions::new(width).justify(column.justify);
+            text::wrap(&cell.spans, &options).into_iter()
+                .map(|line| pad(line, width, Justify::Left))
+                .collect()
+        }
+        Overflow::Truncate => {
+            let options = WrapOptions::new(usize::MAX).break_words(false);
+            text::wrap(&cell.spans, &options).into_iter()
+                .map(|mut line| {
+                    if line.width() > width {
+                        let ellipsis = if unicode { "…" } else { "~" };
+                        let style = line.spans.last().map(|span| span.style).unwrap_or_default();
+                        line.truncate(width.saturating_sub(1));
+                        line.push(Span::new(ellipsis, style));
+                    }
+                    pad(line, width, column.justify)
+                })
+                .collect()
+        }
+    };
+
+    match lines.is_empty() {
+        true => vec![pad(Line::new(), width, Justify::Left)],
+        false => lines,
+    }
+}
+
+/// Pads `line` with spaces to `width` columns, positioning it as `justify` says
+///
+/// A line can only be wider than `width` if a character in it is wider than the whole column, in
+/// which case the character is cut off so that the columns stay lined up.
+fn pad(mut line: Line, width: usize, justify: Justify) -> Line {
+    line.truncate(width);
+
+    let padding = width.saturating_sub(line.width());
+
+    let before = match justify {
+        Justify::Left | Justify::Full => 0,
+        Justify::Right => padding,
+        Justify::Center => padding / 2,
+    };
+
+    let mut padded = Line::from(" ".repeat(before));
+    padded.spans.append(&mut line.spans);
+    padded.push(Span::plain(" ".repeat(padding - before)));
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders `table` as plain text
+    fn rendered(table: &Table, unicode: bool) -> Vec<String> {
+        table.render(unicode).into_iter()
+            .map(|line| {
+                line.into_iter()
+                    .map(|segment| match segment {
+                        Segment::Border(text) => text,
+                        Segment::Cell(line) => line.spans.into_iter().map(|span| span.content).collect(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn columns_are_narrowed_widest_first() {
+        let table = Table::new()
+            .row(["aaaaaaaaaa", "bbbb", "cccccc"])
+            .max_width(18);
+
+        // 4 columns go between the columns, leaving 14 for the text
+        assert_eq!(table.column_widths(), [5, 4, 5]);
+
+        let table = table.column(0, Column::new().min_width(8));
+        assert_eq!(table.column_widths(), [8, 3, 3]);
+
+        // every column is at its minimum width, so the table is left too wide
+        let table = Table::new()
+            .row(["aaaa", "bbbb"])
+            .column(0, Column::new().min_width(3))
+            .max_width(4);
+
+        assert_eq!(table.column_widths(), [3, 1]);
+    }
+
+    #[test]
+    fn long_text_is_truncated() {
+        let table = Table::new()
+            .row(["abcdefgh", "x"])
+            .column(0, Column::new().fixed_width(5));
+
+        assert_eq!(rendered(&table, true), ["abcd…  x"]);
+        assert_eq!(rendered(&table, false), ["abcd~  x"]);
+
+        let table = table.column(0, Column::new().fixed_width(5).justify(Justify::Right));
+        assert_eq!(rendered(&table, true), ["abcd…  x"]);
+    }
+
+    #[test]
+    fn long_text_is_wrapped() {
+        let table = Table::new()
+            .row(["aaa bbb cc", "x"])
+            .column(0, Column::new().fixed_width(5).overflow(Overflow::Wrap));
+
+        assert_eq!(rendered(&table, true), ["aaa    x", "bbb     ", "cc      "]);
+    }
+
+    #[test]
+    fn wide_characters_in_narrow_columns() {
+        for overflow in [Overflow::Truncate, Overflow::Wrap] {
+            let table = Table::new()
+                .row(["漢字", "x"])
+                .column(0, Column::new().fixed_width(1).overflow(overflow))
+                .border(Border::ASCII);
+
+            let lines = rendered(&table, true);
+            assert!(lines.iter().all(|line| str_width(line) == str_width(&lines[0])), "{overflow:?}: {lines:?}");
+        }
+
+        assert_eq!(pad(Line::from("漢"), 1, Justify::Left), Line::from(" "));
+        assert_eq!(pad(Line::from("a"), 3, Justify::Center).width(), 3);
+    }
+
+    #[test]
+    fn ascii_border() {
+        let table = Table::new()
+            .header(["a", "bb"])
+            .row(["ccc", "d"])
+            .border(Border::ASCII);
+
+        assert_eq!(rendered(&table, false), [
+            "+-----+----+",
+            "| a   | bb |",
+            "+-----+----+",
+            "| ccc | d  |",
+            "+-----+----+",
+        ]);
+    }
+}
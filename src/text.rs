@@ -0,0 +1,723 @@
+/*
+    supaterm – terminal manipulation library allowing use of colored text and other functionality is planned
+    Copyright (C) 2026  @polyagonal1
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Styled text and word wrapping
+//!
+//! Text is made of [Span]s, which are pieces of text with a single [ContentStyle]. [wrap] lays a
+//! sequence of spans out into [Line]s no wider than a given number of columns without losing the
+//! styles of the text, and each line can then be queued as a command.
+
+use {
+    crate::{
+        hyperlink::{supports_hyperlinks, Link},
+        command::Context,
+        style::{ContentStyle, ResetStyle},
+        Command,
+        Capability,
+    },
+
+    std::io,
+
+    terminfo::Database,
+};
+
+/// Returns the number of columns `c` takes up when written to a terminal
+///
+/// This is an approximation: control characters and combining marks take no columns, east asian
+/// wide characters and most emoji take two and everything else takes one.
+pub const fn char_width(c: char) -> usize {
+    match c as u32 {
+        // control characters
+        0x00..=0x1F | 0x7F..=0x9F => 0,
+        // combining marks, zero width spaces/joiners and variation selectors
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        // wide characters
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Returns the number of columns `s` takes up when written to a terminal (see [char_width])
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// A piece of text with a single style
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Span {
+    pub content: String,
+    pub style: ContentStyle,
+    /// Makes the text a clickable link, see [hyperlink](crate::hyperlink)
+    pub link: Option<Link>,
+}
+
+impl Span {
+    pub fn new(content: impl Into<String>, style: ContentStyle) -> Self {
+        Self {
+            content: content.into(),
+            style,
+            link: None,
+        }
+    }
+
+    /// Creates a span with no style
+    pub fn plain(content: impl Into<String>) -> Self {
+        Self::new(content, ContentStyle::new())
+    }
+
+    /// Makes the text of this span a link to `link`
+    pub fn link(mut self, link: Link) -> Self {
+        self.link = Some(link);
+        self
+    }
+
+    /// Creates a span with the same style and link as this one
+    fn with_content(&self, content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            style: self.style,
+            link: self.link.clone(),
+        }
+    }
+
+    /// The number of columns this span takes up (see [str_width])
+    pub fn width(&self) -> usize {
+        str_width(&self.content)
+    }
+}
+
+impl Command for Span {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.content.len() + 72)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+        if self.style.is_plain() && self.link.is_none() {
+            return target.write_all(self.content.as_bytes());
+        }
+
+        let links = supports_hyperlinks(database);
+
+        if !self.style.is_plain() {
+            self.style.write_to(database, ctx, target)?;
+        }
+
+        if let Some(link) = &self.link && links {
+            link.write_start(target)?;
+        }
+
+        target.write_all(self.content.as_bytes())?;
+
+        if let Some(link) = &self.link {
+            link.write_end(&self.content, links, target)?;
+        }
+
+        match !self.style.is_plain() && ResetStyle.is_supported(database) {
+            true => ResetStyle.write_to(database, ctx, target),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Capability for Span {
+    fn is_supported(&self, _: &Database) -> bool {
+        true
+    }
+}
+
+/// A single line of styled text
+///
+/// When written as a command, the spans are written one after the other and the style is reset at
+/// the end. No newline is written.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Line {
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    pub const fn new() -> Self {
+        Self {
+            spans: Vec::new(),
+        }
+    }
+
+    /// Appends `span` to the end of the line, merging it into the last span if their styles and
+    /// links match
+    pub fn push(&mut self, span: Span) {
+        if span.content.is_empty() {
+            return;
+        }
+
+        match self.spans.last_mut() {
+            Some(last) if last.style == span.style && last.link == span.link => last.content.push_str(&span.content),
+            _ => self.spans.push(span),
+        }
+    }
+
+    /// The number of columns this line takes up (see [str_width])
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(Span::width).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.iter().all(|span| span.content.is_empty())
+    }
+
+    /// Removes characters from the end of the line until it is at most `width` columns wide
+    pub fn truncate(&mut self, width: usize) {
+        let mut used = 0;
+
+        for (index, span) in self.spans.iter_mut().enumerate() {
+            let end = span.content.char_indices()
+                .find(|&(_, c)| {
+                    used += char_width(c);
+                    used > width
+                })
+                .map(|(end, _)| end);
+
+            if let Some(end) = end {
+                span.content.truncate(end);
+                let keep = index + usize::from(!span.content.is_empty());
+                self.spans.truncate(keep);
+                return;
+            }
+        }
+    }
+}
+
+impl From<Span> for Line {
+    fn from(span: Span) -> Self {
+        let mut line = Self::new();
+        line.push(span);
+        line
+    }
+}
+
+impl From<&str> for Line {
+    fn from(text: &str) -> Self {
+        Span::plain(text).into()
+    }
+}
+
+impl From<String> for Line {
+    fn from(text: String) -> Self {
+        Span::plain(text).into()
+    }
+}
+
+impl Command for Line {
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.spans.iter().map(|span| span.content.len() + 64).sum::<usize>() + 8)
+    }
+
+    fn write_to(&self, database: &Database, ctx: &mut Context, target: &mut dyn io::Write) -> io::Result<()> {
+
+        let links = supports_hyperlinks(database);
+
+        let mut current = ContentStyle::new();
+        let mut link: Option<&Link> = None;
+        // the text of the current link, which is needed for the fallback
+        let mut link_text = String::new();
+
+        for span in &self.spans {
+            if span.link.as_ref() != link {
+                if let Some(link) = link {
+                    link.write_end(&link_text, links, target)?;
+                }
+
+                link = span.link.as_ref();
+                link_text.clear();
+
+                if let Some(link) = link && links {
+                    link.write_start(target)?;
+                }
+            }
+
+            if span.style != current {
+                span.style.write_to(database, ctx, target)?;
+                current = span.style;
+            }
+
+            target.write_all(span.content.as_bytes())?;
+
+            if link.is_some() {
+                link_text.push_str(&span.content);
+            }
+        }
+
+        if let Some(link) = link {
+            link.write_end(&link_text, links, target)?;
+        }
+
+        if !current.is_plain() && ResetStyle.is_supported(database) {
+            ResetStyle.write_to(database, ctx, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Capability for Line {
+    fn is_supported(&self, _: &Database) -> bool {
+        true
+    }
+}
+
+/// How the text in each wrapped line is positioned within the wrapping width
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Left,
+    Right,
+    Center,
+    /// Stretches the gaps between words so that every line fills the wrapping width, except for
+    /// the last line of each paragraph which is left aligned
+    Full,
+}
+
+/// Options describing how [wrap] lays out text
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct WrapOptions {
+    /// The maximum number of columns in a line, including the indent
+    pub width: usize,
+    /// Text put at the start of the first line of every paragraph
+    pub initial_indent: String,
+    /// Text put at the start of every other line
+    pub subsequent_indent: String,
+    pub justify: Justify,
+    /// If true, words which are too long to fit on a line by themselves are broken across lines.
+    /// Otherwise they are put on a line by themselves and overflow the width.
+    pub break_words: bool,
+}
+
+impl WrapOptions {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            initial_indent: String::new(),
+            subsequent_indent: String::new(),
+            justify: Justify::Left,
+            break_words: true,
+        }
+    }
+
+    pub fn initial_indent(mut self, indent: impl Into<String>) -> Self {
+        self.initial_indent = indent.into();
+        self
+    }
+
+    pub fn subsequent_indent(mut self, indent: impl Into<String>) -> Self {
+        self.subsequent_indent = indent.into();
+        self
+    }
+
+    /// Indents every line except the first line of each paragraph by `columns` spaces
+    pub fn hanging_indent(self, columns: usize) -> Self {
+        self.subsequent_indent(" ".repeat(columns))
+    }
+
+    pub fn justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn break_words(mut self, break_words: bool) -> Self {
+        self.break_words = break_words;
+        self
+    }
+}
+
+/// Lays `spans` out into lines that are at most `options.width` columns wide
+///
+/// Lines are broken at whitespace, and whitespace at the point a line is broken is removed.
+/// Newlines in the text are hard breaks which start a new paragraph. The styles of the spans are
+/// kept, including across line breaks.
+pub fn wrap(spans: &[Span], options: &WrapOptions) -> Vec<Line> {
+
+    let spans = identify_links(spans);
+
+    let mut lines = Vec::new();
+    let mut current = PendingLine::new(&options.initial_indent, true);
+    // whitespace seen since the last word which will be written if another word fits on the line
+    let mut gap: Vec<Span> = Vec::new();
+
+    for token in tokenize(&spans) {
+        match token {
+            Token::Newline => {
+                lines.push(current.finish(options, true));
+                current = PendingLine::new(&options.initial_indent, true);
+                gap.clear();
+            }
+            Token::Space(spaces) => {
+                if current.has_words() {
+                    gap.extend(spaces);
+                } else if current.paragraph_start {
+                    // leading whitespace in a paragraph is kept, as it is probably intentional
+                    current.push_word(spaces);
+                }
+            }
+            Token::Word(word) => {
+                let word_width = spans_width(&word);
+                let gap_width = spans_width(&gap);
+
+                if current.has_words() {
+                    if current.width + gap_width + word_width <= options.width {
+                        current.push_gap(std::mem::take(&mut gap));
+                        current.push_word(word);
+                        continue;
+                    }
+
+                    lines.push(current.finish(options, false));
+                    current = PendingLine::new(&options.subsequent_indent, false);
+                }
+
+                gap.clear();
+
+                place_word(word, word_width, &mut current, &mut lines, options);
+            }
+        }
+    }
+
+    if current.has_words() {
+        lines.push(current.finish(options, true));
+    }
+
+    lines
+}
+
+/// Places `word` at the start of `current`, breaking it across lines if necessary
+fn place_word(word: Vec<Span>, word_width: usize, current: &mut PendingLine, lines: &mut Vec<Line>, options: &WrapOptions) {
+
+    if !options.break_words || current.width + word_width <= options.width {
+        current.push_word(word);
+        return;
+    }
+
+    let mut chunk: Vec<Span> = Vec::new();
+    let mut chunk_width = 0;
+
+    for span in word {
+        for c in span.content.chars() {
+            let width = char_width(c);
+
+            // at least one character is put on every line so that this always terminates
+            if current.width + chunk_width + width > options.width && (chunk_width > 0 || current.has_words()) {
+                current.push_word(std::mem::take(&mut chunk));
+                lines.push(std::mem::replace(current, PendingLine::new(&options.subsequent_indent, false)).finish(options, false));
+                chunk_width = 0;
+            }
+
+            push_char(&mut chunk, c, &span);
+            chunk_width += width;
+        }
+    }
+
+    current.push_word(chunk);
+}
+
+fn spans_width(spans: &[Span]) -> usize {
+    spans.iter().map(Span::width).sum()
+}
+
+/// Appends `c` with the style and link of `like` to `spans`, starting a new span if they differ
+/// from the last span
+fn push_char(spans: &mut Vec<Span>, c: char, like: &Span) {
+    match spans.last_mut() {
+        Some(last) if last.style == like.style && last.link == like.link => last.content.push(c),
+        _ => spans.push(like.with_content(c)),
+    }
+}
+
+/// Gives every link in `spans` without an id one, so that links broken across lines are still
+/// treated as one link. Neighbouring spans with the same link are given the same id.
+fn identify_links(spans: &[Span]) -> Vec<Span> {
+    let mut previous: Option<(Link, Link)> = None;
+
+    spans.iter()
+        .cloned()
+        .map(|mut span| {
+            if let Some(link) = &mut span.link && link.id.is_none() {
+                match &previous {
+                    Some((original, identified)) if original == link => *link = identified.clone(),
+                    _ => {
+                        let original = link.clone();
+                        link.ensure_id();
+                        previous = Some((original, link.clone()));
+                    }
+                }
+            }
+
+            span
+        })
+        .collect()
+}
+
+enum Token {
+    Word(Vec<Span>),
+    Space(Vec<Span>),
+    Newline,
+}
+
+/// Splits `spans` into words, runs of whitespace and newlines. A word may be made of several spans
+/// if its style changes part way through.
+fn tokenize(spans: &[Span]) -> Vec<Token> {
+
+    let mut tokens = Vec::new();
+
+    for span in spans {
+        for c in span.content.chars() {
+            match c {
+                '\n' => tokens.push(Token::Newline),
+                // "\r\n" is treated as a single newline
+                '\r' => (),
+                c if c.is_whitespace() => match tokens.last_mut() {
+                    Some(Token::Space(spaces)) => push_char(spaces, c, span),
+                    _ => tokens.push(Token::Space(vec![span.with_content(c)])),
+                },
+                c => match tokens.last_mut() {
+                    Some(Token::Word(word)) => push_char(word, c, span),
+                    _ => tokens.push(Token::Word(vec![span.with_content(c)])),
+                },
+            }
+        }
+    }
+
+    tokens
+}
+
+enum Item {
+    Word(Vec<Span>),
+    Gap(Vec<Span>),
+}
+
+/// A line that is being filled by [wrap]
+struct PendingLine {
+    indent: Span,
+    items: Vec<Item>,
+    /// the width of the line, including the indent
+    width: usize,
+    /// whether this is the first line of a paragraph
+    paragraph_start: bool,
+}
+
+impl PendingLine {
+    fn new(indent: &str, paragraph_start: bool) -> Self {
+        Self {
+            indent: Span::plain(indent),
+            items: Vec::new(),
+            width: str_width(indent),
+            paragraph_start,
+        }
+    }
+
+    fn has_words(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    fn push_word(&mut self, word: Vec<Span>) {
+        self.width += spans_width(&word);
+        self.items.push(Item::Word(word));
+    }
+
+    fn push_gap(&mut self, gap: Vec<Span>) {
+        if gap.is_empty() {
+            return;
+        }
+
+        self.width += spans_width(&gap);
+        self.items.push(Item::Gap(gap));
+    }
+
+    fn finish(mut self, options: &WrapOptions, paragraph_end: bool) -> Line {
+
+        let mut line = Line::new();
+
+        if !self.has_words() {
+            return line;
+        }
+
+        let extra = options.width.saturating_sub(self.width);
+
+        line.push(self.indent);
+
+        match options.justify {
+            Justify::Left => (),
+            Justify::Right => line.push(Span::plain(" ".repeat(extra))),
+            Justify::Center => line.push(Span::plain(" ".repeat(extra / 2))),
+            Justify::Full if paragraph_end => (),
+            Justify::Full => {
+                let gaps = self.items.iter().filter(|item| matches!(item, Item::Gap(_))).count();
+
+                if let Some(per_gap) = extra.checked_div(gaps) {
+                    let mut stretched = 0;
+
+                    for item in &mut self.items {
+                        if let Item::Gap(gap) = item {
+                            let padding = per_gap + usize::from(stretched < extra % gaps);
+                            stretched += 1;
+
+                            if let Some(last) = gap.last_mut() {
+                                last.content.extend(std::iter::repeat_n(' ', padding));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for item in self.items {
+            let (Item::Word(spans) | Item::Gap(spans)) = item;
+
+            for span in spans {
+                line.push(span);
+            }
+        }
+
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::style::Color,
+    };
+
+    /// The text of each line, without styles
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.spans.iter().map(|span| span.content.as_str()).collect()).collect()
+    }
+
+    fn wrap_plain(text: &str, options: &WrapOptions) -> Vec<String> {
+        self::text(&wrap(&[Span::plain(text)], options))
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(str_width("abc"), 3);
+        assert_eq!(str_width("日本"), 4);
+        assert_eq!(str_width("e\u{301}"), 1);
+        assert_eq!(str_width("a\u{200B}b"), 2);
+    }
+
+    #[test]
+    fn wraps_at_whitespace() {
+        assert_eq!(wrap_plain("the quick brown fox", &WrapOptions::new(10)), ["the quick", "brown fox"]);
+        assert_eq!(wrap_plain("one\ntwo three", &WrapOptions::new(20)), ["one", "two three"]);
+    }
+
+    #[test]
+    fn line_exactly_the_width() {
+        assert_eq!(wrap_plain("abcde fghij", &WrapOptions::new(5)), ["abcde", "fghij"]);
+        assert_eq!(wrap_plain("abc de", &WrapOptions::new(6)), ["abc de"]);
+    }
+
+    #[test]
+    fn wide_characters() {
+        // each character takes two columns, so only two fit in five columns
+        assert_eq!(wrap_plain("日本語 日本", &WrapOptions::new(5)), ["日本", "語", "日本"]);
+        assert_eq!(wrap_plain("日本 語", &WrapOptions::new(6)), ["日本", "語"]);
+    }
+
+    #[test]
+    fn zero_width_characters() {
+        assert_eq!(wrap_plain("cafe\u{301} abc", &WrapOptions::new(4)), ["cafe\u{301}", "abc"]);
+        assert_eq!(wrap_plain("ab\u{200B}\u{200B}cd", &WrapOptions::new(4)), ["ab\u{200B}\u{200B}cd"]);
+    }
+
+    #[test]
+    fn break_words() {
+        assert_eq!(wrap_plain("abcdefghij k", &WrapOptions::new(4)), ["abcd", "efgh", "ij k"]);
+        assert_eq!(wrap_plain("abcdefghij k", &WrapOptions::new(4).break_words(false)), ["abcdefghij", "k"]);
+    }
+
+    #[test]
+    fn indents() {
+        let options = WrapOptions::new(10).initial_indent("- ").hanging_indent(2);
+        assert_eq!(wrap_plain("one two three four", &options), ["- one two", "  three", "  four"]);
+    }
+
+    #[test]
+    fn justify() {
+        let options = |justify| WrapOptions::new(10).justify(justify);
+
+        assert_eq!(wrap_plain("ab cd ef", &options(Justify::Right)), ["  ab cd ef"]);
+        assert_eq!(wrap_plain("ab cd ef", &options(Justify::Center)), [" ab cd ef"]);
+
+        // spare columns go to the first gaps, and the last line of each paragraph isn't stretched
+        assert_eq!(wrap_plain("a bb cc ddd eee\nf g", &options(Justify::Full)), ["a   bb  cc", "ddd eee", "f g"]);
+    }
+
+    #[test]
+    fn styles_are_kept() {
+        let red = ContentStyle::new().foreground(Color::Red);
+        let lines = wrap(&[Span::plain("one "), Span::new("two three", red)], &WrapOptions::new(7));
+
+        assert_eq!(lines, [
+            Line { spans: vec![Span::plain("one "), Span::new("two", red)] },
+            Line { spans: vec![Span::new("three", red)] },
+        ]);
+    }
+
+    #[test]
+    fn truncate() {
+        let red = ContentStyle::new().foreground(Color::Red);
+        let line = Line { spans: vec![Span::plain("ab"), Span::new("cdef", red)] };
+
+        let truncated = |width| {
+            let mut line = line.clone();
+            line.truncate(width);
+            line
+        };
+
+        assert_eq!(truncated(6), line);
+        assert_eq!(truncated(4), Line { spans: vec![Span::plain("ab"), Span::new("cd", red)] });
+        assert_eq!(truncated(2), Line { spans: vec![Span::plain("ab")] });
+        assert_eq!(truncated(0), Line::new());
+    }
+
+    #[test]
+    fn truncate_wide_and_zero_width_characters() {
+        let truncated = |text: &str, width| {
+            let mut line = Line::from(text);
+            line.truncate(width);
+            self::text(&[line]).remove(0)
+        };
+
+        // a wide character which would only half fit is removed
+        assert_eq!(truncated("日本", 3), "日");
+        assert_eq!(truncated("日本", 4), "日本");
+        // zero width characters are kept with the character before them
+        assert_eq!(truncated("e\u{301}x", 1), "e\u{301}");
+    }
+}